@@ -47,7 +47,22 @@ impl Hittable for XyRect {
         let rec_u = (x-self.x0)/(self.x1-self.x0);
         let rec_v = (y-self.y0)/(self.y1-self.y0);
         let outward_normal = Vec3::fwd();
-        Some(HitRecord::new(r, outward_normal, self.mp.clone(), t, rec_u, rec_v))
+        let mut rec = HitRecord::new(r, outward_normal, self.mp.clone(), t, rec_u, rec_v);
+        rec.set_tangents(Vec3::new(self.x1-self.x0, 0.0, 0.0), Vec3::new(0.0, self.y1-self.y0, 0.0));
+        Some(rec)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mp.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        let area = (self.x1-self.x0)*(self.y1-self.y0);
+        area_pdf(self, area, &Vec3::fwd(), origin, direction, rng)
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut RngGen) -> Vec3 {
+        Point3::new(rng.range(self.x0, self.x1), rng.range(self.y0, self.y1), self.k) - origin
     }
 }
 
@@ -90,7 +105,22 @@ impl Hittable for YzRect {
         let rec_u = (y-self.y0)/(self.y1-self.y0);
         let rec_v = (z-self.z0)/(self.z1-self.z0);
         let outward_normal = Vec3::right();
-        Some(HitRecord::new(r, outward_normal, self.mp.clone(), t, rec_u, rec_v))
+        let mut rec = HitRecord::new(r, outward_normal, self.mp.clone(), t, rec_u, rec_v);
+        rec.set_tangents(Vec3::new(0.0, self.y1-self.y0, 0.0), Vec3::new(0.0, 0.0, self.z1-self.z0));
+        Some(rec)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mp.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        let area = (self.y1-self.y0)*(self.z1-self.z0);
+        area_pdf(self, area, &Vec3::right(), origin, direction, rng)
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut RngGen) -> Vec3 {
+        Point3::new(self.k, rng.range(self.y0, self.y1), rng.range(self.z0, self.z1)) - origin
     }
 }
 
@@ -133,6 +163,35 @@ impl Hittable for ZxRect {
         let rec_u = (x-self.x0)/(self.x1-self.x0);
         let rec_v = (z-self.z0)/(self.z1-self.z0);
         let outward_normal = Vec3::up();
-        Some(HitRecord::new(r, outward_normal, self.mp.clone(), t, rec_u, rec_v))
+        let mut rec = HitRecord::new(r, outward_normal, self.mp.clone(), t, rec_u, rec_v);
+        rec.set_tangents(Vec3::new(self.x1-self.x0, 0.0, 0.0), Vec3::new(0.0, 0.0, self.z1-self.z0));
+        Some(rec)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mp.is_emissive()
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        let area = (self.z1-self.z0)*(self.x1-self.x0);
+        area_pdf(self, area, &Vec3::up(), origin, direction, rng)
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut RngGen) -> Vec3 {
+        Point3::new(rng.range(self.x0, self.x1), self.k, rng.range(self.z0, self.z1)) - origin
+    }
+}
+
+// Converts the uniform area density of a flat light into solid angle density
+fn area_pdf(rect: &dyn Hittable, area: Float, normal: &Vec3,
+            origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+    let ray = Ray::new(origin, direction, 0.0);
+    match rect.hit(&ray, 0.001, Float::INFINITY, rng) {
+        Some(hit) => {
+            let distance_squared = hit.t()*hit.t()*direction.length_squared();
+            let cosine = (Vec3::dot(direction, normal) / direction.length()).abs();
+            if cosine <= 0.0 { 0.0 } else { distance_squared / (cosine*area) }
+        },
+        None => 0.0
     }
 }
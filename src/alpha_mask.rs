@@ -0,0 +1,66 @@
+use super::float::Float;
+use super::vec3::Vec3;
+type Point3 = Vec3;
+use super::rng_float::RngGen;
+use super::ray::Ray;
+use super::hittable::{Hittable, HitRecord};
+use super::hittable_list::HittableList;
+use super::texture::Texture;
+use super::aabb::AABB;
+
+use std::rc::Rc;
+
+// Cutout for `source`, where `mask` is transparent rays go through the
+// surface as if it wasn't there. Partial opacity lets through that fraction
+// of the rays.
+pub struct AlphaMask {
+    source: Rc<dyn Hittable>,
+    mask: Rc<dyn Texture>,
+}
+
+impl AlphaMask {
+    pub fn new(source: Rc<dyn Hittable>, mask: Rc<dyn Texture>) -> Self {
+        Self { source, mask }
+    }
+}
+
+impl Hittable for AlphaMask {
+    fn hit(&self, r:&Ray, t_min:Float, t_max:Float, rng: &mut RngGen) -> Option<HitRecord> {
+        let mut t_min = t_min;
+        loop {
+            let hit = self.source.hit(r, t_min, t_max, rng)?;
+            let alpha = self.mask.alpha(hit.u(), hit.v(), hit.p());
+            if alpha >= 1.0 || alpha > rng.get() {
+                return Some(hit)
+            }
+            t_min = hit.t() + 0.0001;
+        }
+    }
+
+    fn bounding_box(&self, time0: Float, time1: Float) -> Option<AABB> {
+        self.source.bounding_box(time0, time1)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.source.is_emissive()
+    }
+
+    // Lights inside get cut out by the same mask
+    fn collect_lights(&self, lights: &mut HittableList) {
+        let mut inside = HittableList::new();
+        self.source.collect_lights(&mut inside);
+        for light in inside.objects() {
+            lights.add(Rc::new(AlphaMask::new(light.clone(), self.mask.clone())));
+        }
+    }
+
+    // NOTE(srp): Samples land on the whole source, the ones the mask cuts out
+    // miss the light and add nothing.
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        self.source.pdf_value(origin, direction, rng)
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut RngGen) -> Vec3 {
+        self.source.random(origin, rng)
+    }
+}
@@ -0,0 +1,92 @@
+use super::float::{Float, PI};
+use super::vec3::Vec3;
+use super::rng_float::RngGen;
+use super::distribution::Distribution;
+
+use stb_image::image::{LoadResult, load_with_depth};
+use std::path::Path;
+
+// Shape of the lens opening, out of focus highlights (bokeh) take this shape
+pub enum Aperture {
+    Circle,
+    Polygon(u32, Float), // Number of blades and rotation in degrees
+    Mask(ApertureMask),
+}
+
+impl Aperture {
+    // Point on the aperture, inside the unit disk on the xy plane
+    pub fn sample(&self, rng: &mut RngGen) -> Vec3 {
+        match self {
+            Aperture::Circle => Vec3::random_unit_xy(rng),
+            Aperture::Polygon(blades, rotation) => {
+                // Uniform on one of the triangles between the center and a blade
+                let blades = (*blades).max(3);
+                let k = ((rng.get() * blades as Float) as u32).min(blades - 1);
+                let corner = |i: u32| {
+                    let angle = rotation.to_radians() + 2.0*PI * i as Float / blades as Float;
+                    Vec3::new(angle.cos(), angle.sin(), 0.0)
+                };
+                let (mut a, mut b) = (rng.get(), rng.get());
+                if a + b > 1.0 {
+                    a = 1.0 - a;
+                    b = 1.0 - b;
+                }
+                a*corner(k) + b*corner(k + 1)
+            },
+            Aperture::Mask(mask) => mask.sample(rng)
+        }
+    }
+}
+
+// Grayscale image of the aperture, brighter pixels let more light through.
+// The image's inscribed circle matches a round aperture of the same size.
+pub struct ApertureMask {
+    width: usize,
+    height: usize,
+    rows: Distribution,
+    columns: Vec<Distribution>,
+}
+
+impl ApertureMask {
+    pub fn new(path: &Path) -> Self {
+        let (width, height, pixels): (usize, usize, Vec<Float>) = match load_with_depth(path, 1, false) {
+            LoadResult::ImageU8(img) => {
+                let pixels = img.data.iter().step_by(img.depth).map(|&p| p as Float).collect();
+                (img.width, img.height, pixels)
+            },
+            LoadResult::ImageF32(img) => {
+                let pixels = img.data.iter().step_by(img.depth).copied().collect();
+                (img.width, img.height, pixels)
+            },
+            LoadResult::Error(e) => {
+                // Missing mask, fall back to a round opening
+                eprintln!("Couldn't load aperture mask {}: {}", path.display(), e);
+                const SIZE: usize = 64;
+                let pixels = (0..SIZE*SIZE).map(|n| {
+                    let x = 2.0 * ((n % SIZE) as Float + 0.5) / SIZE as Float - 1.0;
+                    let y = 2.0 * ((n / SIZE) as Float + 0.5) / SIZE as Float - 1.0;
+                    if x*x + y*y <= 1.0 { 1.0 } else { 0.0 }
+                }).collect();
+                (SIZE, SIZE, pixels)
+            }
+        };
+
+        let columns: Vec<Distribution> = (0..height)
+            .map(|j| Distribution::new(pixels[j*width..(j+1)*width].to_vec()))
+            .collect();
+        let rows = Distribution::new(columns.iter().map(|c| c.integral()).collect());
+        Self { width, height, rows, columns }
+    }
+
+    fn sample(&self, rng: &mut RngGen) -> Vec3 {
+        let (v, j) = self.rows.sample(rng.get());
+        let (u, _) = self.columns[j].sample(rng.get());
+        // Image rows go down, the aperture's y goes up. Non square images
+        // keep their proportions.
+        let size = self.width.max(self.height) as Float;
+        Vec3::new(
+            (2.0*u - 1.0) * self.width as Float / size,
+            (1.0 - 2.0*v) * self.height as Float / size,
+            0.0)
+    }
+}
@@ -35,6 +35,10 @@ impl Hittable for BVH {
     fn hit(&self, r:&Ray, t_min:Float, t_max:Float, rng: &mut RngGen) -> Option<HitRecord> {
         self.root.hit(r, t_min, t_max, rng)
     }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        self.root.collect_lights(lights)
+    }
 }
 
 impl Node {
@@ -120,6 +124,14 @@ impl Hittable for Node {
         if let None = hit_right { hit_left } else { hit_right }
     }
 
+    fn collect_lights(&self, lights: &mut HittableList) {
+        lights.add_lights_from(&self.left);
+        // NOTE(srp): Leaves with a single object store it on both sides
+        if !Rc::ptr_eq(&self.left, &self.right) {
+            lights.add_lights_from(&self.right);
+        }
+    }
+
     fn bounding_box(&self, _time0: Float, _time1: Float) -> Option<AABB> {
         Some(self.aabb.copy())
     }
@@ -3,6 +3,11 @@ use super::vec3::Vec3;
 type Point3 = Vec3;
 use super::ray::Ray;
 use super::rng_float::RngGen;
+use super::aperture::Aperture;
+use super::color::PHOTOMETRIC_SCALE;
+use super::lens_system::LensSystem;
+
+use std::rc::Rc;
 
 pub struct Camera {
     origin: Point3,
@@ -13,8 +18,233 @@ pub struct Camera {
     v: Vec3,
     w: Vec3,
     lens_radius: Float,
+    aperture_shape: Rc<Aperture>,
     shutter_open_time: Float,
     shutter_close_time: Float,
+    shutter_curve: ShutterCurve,
+    readout_time: Float,
+    projection: Projection,
+    aspect_ratio: Float,
+    eye_offset: Float, // Along `u`, negative for the left eye
+    convergence: Float,
+    exposure: Float,
+    lens: Option<LensCamera>,
+    lens_shift: (Float, Float),
+    focal_plane_normal: Option<Vec3>,
+    focus_dist: Float,
+    distortion: [Float; 5],
+    viewport: (Float, Float), // Size at distance one
+}
+
+// Lens system focused for one camera, with its film fitted to the image
+struct LensCamera {
+    system: LensSystem,
+    film_width: Float, // mm
+    film_height: Float, // mm
+    mm_to_scene: Float,
+    center_transmission: Float,
+}
+
+impl LensCamera {
+    fn new(data: &CameraBuilder, system: &LensSystem, aspect_ratio: Float) -> Self {
+        let (sensor_width, sensor_height, units_per_meter) = match data.physical.as_ref() {
+            Some(physical) => (physical.sensor_width, physical.sensor_height, physical.units_per_meter),
+            None => (36.0, 24.0, 1.0)
+        };
+        let film_height = sensor_height.min(sensor_width / aspect_ratio);
+        let film_width = aspect_ratio * film_height;
+        let mm_to_scene = 0.001 * units_per_meter;
+        let film_diagonal = (film_width*film_width + film_height*film_height).sqrt();
+        let system = system.focused(data.focus_dist / mm_to_scene, film_diagonal)
+            .unwrap_or_else(|e| {
+                eprintln!("Couldn't focus the lens system, it stays as it is: {}", e);
+                system.clone()
+            });
+
+        // Average weight at the center of the film, before normalizing
+        const STEPS: usize = 32;
+        let rear_radius = system.rear_aperture_radius();
+        let mut inside = 0;
+        let mut passed = 0.0;
+        for i in 0..STEPS {
+            for j in 0..STEPS {
+                let x = 2.0 * (i as Float + 0.5) / STEPS as Float - 1.0;
+                let y = 2.0 * (j as Float + 0.5) / STEPS as Float - 1.0;
+                if x*x + y*y > 1.0 {
+                    continue
+                }
+                inside += 1;
+                let rear = Vec3::new(rear_radius * x, rear_radius * y, system.rear_z());
+                if system.trace_from_film(&Vec3::zero(), &rear).is_some() {
+                    passed += cos4_theta(&rear);
+                }
+            }
+        }
+        let center_transmission = (passed / inside as Float).max(Float::EPSILON);
+
+        Self { system, film_width, film_height, mm_to_scene, center_transmission }
+    }
+
+    // Ray in lens space and its weight
+    fn sample(&self, s: Float, t: Float, rng: &mut RngGen) -> Option<(Vec3, Vec3, Float)> {
+        // The lens flips the image both ways
+        let film = Vec3::new(-(s - 0.5) * self.film_width, -(t - 0.5) * self.film_height, 0.0);
+        let rear = self.system.rear_aperture_radius() * Vec3::random_unit_xy(rng)
+            + Vec3::new(0.0, 0.0, self.system.rear_z());
+        let direction = rear - &film;
+        let (origin, direction_out) = self.system.trace_from_film(&film, &direction)?;
+        // NOTE(srp): cos^4 falloff like pbrt's simple weighting, normalized
+        // so the center of the image is as bright as with a thin lens.
+        let weight = cos4_theta(&direction) / self.center_transmission;
+        Some((origin, direction_out, weight))
+    }
+}
+
+fn cos4_theta(direction: &Vec3) -> Float {
+    let cos_theta = direction.unit_vector().z();
+    cos_theta*cos_theta*cos_theta*cos_theta
+}
+
+#[derive(Clone, Copy)]
+pub enum Projection {
+    Perspective,
+    Orthographic(Float), // Height of the view plane in world units
+    Equirectangular, // Whole sphere, laid out like an EnvironmentMap
+    Fisheye(Float), // Equidistant, field of view across the image circle in degrees
+}
+
+// How far open the shutter is while it's open, which spreads motion blur
+// differently along the path of moving objects
+#[derive(Clone, Copy)]
+pub enum ShutterCurve {
+    Box, // Fully open right away
+    Trapezoid(Float, Float), // Fractions of the exposure spent opening and closing
+}
+
+impl ShutterCurve {
+    // Point in the exposure, from 0 to 1, with the chance of the shutter
+    // being that far open there
+    fn sample(&self, u: Float) -> Float {
+        match *self {
+            ShutterCurve::Box => u,
+            ShutterCurve::Trapezoid(opening, closing) => {
+                let opening = opening.clamp(0.0, 1.0);
+                let closing = closing.clamp(0.0, 1.0 - opening);
+                // Picks the opening ramp, the fully open part or the closing
+                // ramp by area, then inverts that piece
+                let u = u * self.efficiency();
+                let full = 1.0 - opening - closing;
+                if u < 0.5*opening {
+                    (2.0 * u * opening).sqrt()
+                } else if u < 0.5*opening + full {
+                    opening + (u - 0.5*opening)
+                } else {
+                    let left = (self.efficiency() - u).max(0.0);
+                    1.0 - (2.0 * left * closing).sqrt()
+                }
+            }
+        }
+    }
+
+    // Light let through compared to a shutter that opens instantly
+    pub fn efficiency(&self) -> Float {
+        match *self {
+            ShutterCurve::Box => 1.0,
+            ShutterCurve::Trapezoid(opening, closing) => {
+                let opening = opening.clamp(0.0, 1.0);
+                let closing = closing.clamp(0.0, 1.0 - opening);
+                1.0 - 0.5*(opening + closing)
+            }
+        }
+    }
+}
+
+// Photographic settings, they replace the field of view and aperture and add
+// an exposure
+pub struct PhysicalCamera {
+    focal_length: Float, // mm
+    sensor_width: Float, // mm
+    sensor_height: Float, // mm
+    f_number: Float,
+    shutter: Float, // Seconds
+    iso: Float,
+    units_per_meter: Float,
+}
+
+// NOTE(srp): ISO 12232 saturation based sensitivity, K = 78 and q = 0.65.
+// With the sunny 16 rule a white surface in the sun comes out close to one.
+const SATURATION_CONSTANT: Float = 78.0;
+const LENS_TRANSMISSION: Float = 0.65;
+
+impl PhysicalCamera {
+    pub fn new(focal_length: Float, f_number: Float, shutter: Float, iso: Float) -> Self {
+        Self {
+            focal_length,
+            sensor_width: 36.0,
+            sensor_height: 24.0,
+            f_number,
+            shutter,
+            iso,
+            units_per_meter: 1.0,
+        }
+    }
+
+    // Full frame (36x24mm) by default
+    pub fn sensor(&mut self, width: Float, height: Float) -> &mut Self {
+        self.sensor_width = width;
+        self.sensor_height = height;
+        self
+    }
+
+    // Size of a meter in scene units, for the lens opening
+    pub fn units_per_meter(&mut self, units_per_meter: Float) -> &mut Self {
+        self.units_per_meter = units_per_meter;
+        self
+    }
+
+    // The image is cropped to fit inside the sensor
+    fn vertical_fov(&self, aspect_ratio: Float) -> Float {
+        let height = self.sensor_height.min(self.sensor_width / aspect_ratio);
+        2.0 * (0.5 * height / self.focal_length).atan().to_degrees()
+    }
+
+    fn lens_radius(&self) -> Float {
+        0.5 * (self.focal_length / self.f_number) * 0.001 * self.units_per_meter
+    }
+
+    // Radiance to pixel value
+    fn exposure(&self) -> Float {
+        let per_luminance = LENS_TRANSMISSION * self.shutter * self.iso
+            / (SATURATION_CONSTANT * self.f_number * self.f_number);
+        per_luminance / PHOTOMETRIC_SCALE
+    }
+}
+
+// Where each eye goes in a stereo image, the left eye is on the left or on top
+#[derive(Clone, Copy)]
+pub enum StereoLayout {
+    SideBySide,
+    OverUnder,
+}
+
+impl StereoLayout {
+    // Eye index (0 left, 1 right) and its own image coordinates for a point
+    // of the whole image
+    pub fn eye_uv(&self, s: Float, t: Float) -> (usize, Float, Float) {
+        match self {
+            StereoLayout::SideBySide if s < 0.5 => (0, 2.0*s, t),
+            StereoLayout::SideBySide => (1, 2.0*s - 1.0, t),
+            StereoLayout::OverUnder if t >= 0.5 => (0, s, 2.0*t - 1.0),
+            StereoLayout::OverUnder => (1, s, 2.0*t),
+        }
+    }
+
+    fn eye_aspect_ratio(&self, aspect_ratio: Float) -> Float {
+        match self {
+            StereoLayout::SideBySide => 0.5 * aspect_ratio,
+            StereoLayout::OverUnder => 2.0 * aspect_ratio,
+        }
+    }
 }
 
 pub struct CameraBuilder {
@@ -24,9 +254,21 @@ pub struct CameraBuilder {
     vertical_fov: Float,
     aspect_ratio: Float,
     aperture: Float,
+    aperture_shape: Rc<Aperture>,
+    physical: Option<Rc<PhysicalCamera>>,
+    lens_system: Option<Rc<LensSystem>>,
     focus_dist: Float,
     shutter_open_time: Float,
     shutter_close_time: Float,
+    shutter_curve: ShutterCurve,
+    readout_time: Float,
+    projection: Projection,
+    lens_shift: (Float, Float),
+    tilt: (Float, Float),
+    distortion: [Float; 5],
+    interocular: Float,
+    convergence: Float,
+    stereo_layout: Option<StereoLayout>,
 }
 
 impl CameraBuilder {
@@ -40,10 +282,15 @@ impl CameraBuilder {
         let focus_dist = (&lookfrom - &lookat).length();
         let shutter_open_time = 0.0;
         let shutter_close_time = 0.0;
+        let projection = Projection::Perspective;
 
         CameraBuilder {
             lookfrom, lookat, view_up, vertical_fov, aspect_ratio,
-            aperture, focus_dist, shutter_open_time, shutter_close_time
+            aperture, aperture_shape: Rc::new(Aperture::Circle), physical: None, lens_system: None,
+            focus_dist, shutter_open_time, shutter_close_time,
+            shutter_curve: ShutterCurve::Box, readout_time: 0.0, projection,
+            lens_shift: (0.0, 0.0), tilt: (0.0, 0.0), distortion: [0.0; 5],
+            interocular: 0.0, convergence: Float::INFINITY, stereo_layout: None
         }
     }
 
@@ -77,6 +324,29 @@ impl CameraBuilder {
         self
     }
 
+    // `aperture` is the diameter of the circle around the shape
+    pub fn aperture_shape(&mut self, aperture_shape: Aperture) -> &mut Self {
+        self.aperture_shape = Rc::new(aperture_shape);
+        self
+    }
+
+    // NOTE(srp): Motion blur still uses the shutter times, scenes don't
+    // measure time in seconds.
+    pub fn physical(&mut self, physical: PhysicalCamera) -> &mut Self {
+        self.physical = Some(Rc::new(physical));
+        self
+    }
+
+    // Traces perspective rays through the lens elements instead of a thin
+    // lens. The lens moves to focus at `focus_dist` from `lookfrom`, the film
+    // and scale come from the physical settings (full frame in meters
+    // otherwise). The field of view and aperture are left out, and building
+    // fails if stereo, an aperture shape, tilt-shift or distortion are set.
+    pub fn lens_system(&mut self, lens_system: LensSystem) -> &mut Self {
+        self.lens_system = Some(Rc::new(lens_system));
+        self
+    }
+
     pub fn focus_dist(&mut self, focus_dist: Float) -> &mut Self {
         self.focus_dist = focus_dist;
         self
@@ -92,46 +362,393 @@ impl CameraBuilder {
         self
     }
 
+    // Physical cameras lose the light the shutter blocks while it moves
+    pub fn shutter_curve(&mut self, shutter_curve: ShutterCurve) -> &mut Self {
+        self.shutter_curve = shutter_curve;
+        self
+    }
+
+    // Scene time it takes to read the image from the top row to the bottom
+    // one, each row is exposed that much later than the one above it. Zero
+    // is a global shutter. It comes out of the shutter window, so every row
+    // is exposed for the window minus the readout and the last one closes
+    // at `shutter_close_time`.
+    pub fn rolling_shutter(&mut self, readout_time: Float) -> &mut Self {
+        self.readout_time = readout_time;
+        self
+    }
+
+    // Only perspective cameras use the field of view, aperture, focus,
+    // tilt-shift and distortion
+    pub fn projection(&mut self, projection: Projection) -> &mut Self {
+        self.projection = projection;
+        self
+    }
+
+    // Slides the image off the lens axis, in fractions of its width and
+    // height. Shifting up keeps verticals straight when looking up a building.
+    pub fn lens_shift(&mut self, x: Float, y: Float) -> &mut Self {
+        self.lens_shift = (x, y);
+        self
+    }
+
+    // Angles in degrees of the plane in focus (not of the lens), it turns
+    // around the point at `focus_dist`. Positive tilt brings the focus closer
+    // at the bottom of the image and positive swing on the left.
+    pub fn tilt(&mut self, tilt: Float, swing: Float) -> &mut Self {
+        self.tilt = (tilt, swing);
+        self
+    }
+
+    // Brown-Conrady coefficients, k are radial and p tangential. The image
+    // comes out distorted the way a lens with them would distort it.
+    pub fn distortion(&mut self, k1: Float, k2: Float, k3: Float, p1: Float, p2: Float) -> &mut Self {
+        self.distortion = [k1, k2, k3, p1, p2];
+        self
+    }
+
+    // Objects `convergence` away from `lookfrom` line up in both eyes.
+    // Equirectangular cameras render omni-directional stereo (ODS).
+    // Orthographic cameras see the same from both eyes.
+    pub fn stereo(&mut self, interocular: Float, convergence: Float, layout: StereoLayout) -> &mut Self {
+        self.interocular = interocular;
+        self.convergence = convergence;
+        self.stereo_layout = Some(layout);
+        self
+    }
+
+    fn uses_lens_system(&self) -> bool {
+        self.lens_system.is_some() && matches!(self.projection, Projection::Perspective)
+    }
+
+    // NOTE(srp): These only exist for the thin lens, traced lenses have no
+    // place to put them. The camera is built without them, saying so.
+    fn warn_lens_system_conflicts(&self) {
+        if !self.uses_lens_system() {
+            return
+        }
+        let mut unsupported = Vec::new();
+        if self.stereo_layout.is_some() {
+            unsupported.push("stereo");
+        }
+        if !matches!(*self.aperture_shape, Aperture::Circle) {
+            unsupported.push("aperture shapes");
+        }
+        if self.lens_shift != (0.0, 0.0) {
+            unsupported.push("lens shift");
+        }
+        if self.tilt != (0.0, 0.0) {
+            unsupported.push("tilt");
+        }
+        if self.distortion.iter().any(|&c| c != 0.0) {
+            unsupported.push("distortion");
+        }
+        if !unsupported.is_empty() {
+            eprintln!("Lens systems can't be combined with {}, ignoring them", unsupported.join(", "));
+        }
+    }
+
+    // Lens systems only render a single view
+    pub fn stereo_layout(&self) -> Option<StereoLayout> {
+        if self.uses_lens_system() {
+            return None
+        }
+        self.stereo_layout
+    }
+
     pub fn build(&self) -> Camera {
-        Camera::new(self)
+        self.warn_lens_system_conflicts();
+        Camera::new(self, self.aspect_ratio, 0.0)
+    }
+
+    // One camera, or the left and right eyes for stereo
+    pub fn build_views(&self) -> Vec<Camera> {
+        match self.stereo_layout() {
+            None => vec![self.build()],
+            Some(layout) => {
+                let aspect_ratio = layout.eye_aspect_ratio(self.aspect_ratio);
+                let half = 0.5 * self.interocular;
+                vec![Camera::new(self, aspect_ratio, -half), Camera::new(self, aspect_ratio, half)]
+            }
+        }
     }
 }
 
 impl Camera {
-    fn new(data: &CameraBuilder) -> Self {
-        let theta = data.vertical_fov.to_radians();
+    fn new(data: &CameraBuilder, aspect_ratio: Float, eye_offset: Float) -> Self {
+        let (vertical_fov, aperture, exposure) = match data.physical.as_ref() {
+            Some(physical) =>
+                (physical.vertical_fov(aspect_ratio), 2.0 * physical.lens_radius(),
+                 physical.exposure() * data.shutter_curve.efficiency()),
+            None => (data.vertical_fov, data.aperture, 1.0)
+        };
+        let theta = vertical_fov.to_radians();
         let h = (theta * 0.5).tan();
         let viewport_height: Float = 2.0 * h;
-        let viewport_width: Float = data.aspect_ratio * viewport_height;
+        let viewport_width: Float = aspect_ratio * viewport_height;
         
         let w = (&data.lookfrom - &data.lookat).unit_vector();
         let u = Vec3::cross(&data.view_up, &w).unit_vector();
         let v = Vec3::cross(&w, &u);
 
         let origin = data.lookfrom.copy();
-        let horizontal = &data.focus_dist * viewport_width * &u;
-        let vertical = &data.focus_dist * viewport_height * &v;
-        let lower_left_corner = &origin - &horizontal/2.0 - &vertical/2.0 - data.focus_dist*&w;
-        let lens_radius = data.aperture / 2.0;
+        let (horizontal, vertical, lower_left_corner, lens_radius) = match data.projection {
+            Projection::Perspective => {
+                let horizontal = &data.focus_dist * viewport_width * &u;
+                let vertical = &data.focus_dist * viewport_height * &v;
+                let (shift_x, shift_y) = data.lens_shift;
+                let lower_left_corner = &origin + (shift_x - 0.5)*&horizontal + (shift_y - 0.5)*&vertical
+                    - data.focus_dist*&w;
+                (horizontal, vertical, lower_left_corner, aperture / 2.0)
+            },
+            Projection::Orthographic(height) => {
+                // View plane through `lookfrom`
+                let horizontal = aspect_ratio * height * &u;
+                let vertical = height * &v;
+                let lower_left_corner = &origin - &horizontal/2.0 - &vertical/2.0;
+                (horizontal, vertical, lower_left_corner, 0.0)
+            },
+            Projection::Equirectangular | Projection::Fisheye(_) =>
+                (Vec3::zero(), Vec3::zero(), origin.copy(), 0.0)
+        };
         let shutter_open_time = data.shutter_open_time;
         let shutter_close_time = data.shutter_close_time;
 
-        Camera {
+        let (tilt, swing) = (data.tilt.0.to_radians(), data.tilt.1.to_radians());
+        let focal_plane_normal = if tilt != 0.0 || swing != 0.0 {
+            Some((tilt.cos()*swing.cos()*&w + tilt.sin()*&v + swing.sin()*&u).unit_vector())
+        } else {
+            None
+        };
+
+        let mut camera = Camera {
             origin, lower_left_corner, horizontal, vertical, u, v, w,
-            lens_radius, shutter_open_time, shutter_close_time
+            lens_radius, aperture_shape: data.aperture_shape.clone(),
+            shutter_open_time, shutter_close_time,
+            shutter_curve: data.shutter_curve, readout_time: data.readout_time,
+            projection: data.projection, aspect_ratio, eye_offset, convergence: data.convergence,
+            exposure, lens: None, lens_shift: data.lens_shift, focal_plane_normal,
+            focus_dist: data.focus_dist, distortion: data.distortion,
+            viewport: (viewport_width, viewport_height)
+        };
+        if let (Projection::Perspective, Some(system)) = (data.projection, data.lens_system.as_ref()) {
+            camera.lens = Some(LensCamera::new(data, system, aspect_ratio));
+            return camera
+        }
+        if let Projection::Perspective = data.projection {
+            // NOTE(srp): Off-axis eyes, the viewport slides so both eyes frame
+            // the plane at the convergence distance the same way.
+            let shift = eye_offset * (1.0 - data.focus_dist / data.convergence);
+            camera.origin += eye_offset * &camera.u;
+            camera.lower_left_corner += shift * &camera.u;
         }
+        camera
+    }
+
+    pub fn exposure(&self) -> Float {
+        self.exposure
+    }
+
+    // Time during the exposure of the row at height `t`
+    fn ray_time(&self, t: Float, rng: &mut RngGen) -> Float {
+        let window = self.shutter_close_time - self.shutter_open_time;
+        let readout_time = self.readout_time.clamp(0.0, window.max(0.0));
+        let row_delay = (1.0 - t).clamp(0.0, 1.0) * readout_time;
+        let exposure_time = window - readout_time;
+        self.shutter_open_time + row_delay + self.shutter_curve.sample(rng.get()) * exposure_time
     }
 
     fn get_ray_dir(&self, s: Float, t: Float, offset: &Vec3) -> Vec3 {
-        &self.lower_left_corner + s*&self.horizontal + t*&self.vertical - &self.origin - offset
+        let target = &self.lower_left_corner + s*&self.horizontal + t*&self.vertical;
+        if let Some(normal) = self.focal_plane_normal.as_ref() {
+            // Where the ray through the center of the lens meets the tilted
+            // plane. Rays that never meet it, above the horizon of a strong
+            // tilt, are focused at infinity: every point of the lens looks
+            // the same way. The target runs off to infinity as they get
+            // close, so the focus doesn't jump.
+            let chief = &target - &self.origin;
+            let along = Vec3::dot(&chief, normal);
+            if along >= 0.0 {
+                return chief
+            }
+            let distance = -self.focus_dist * Vec3::dot(&self.w, normal);
+            return (distance / along) * chief - offset
+        }
+        target - &self.origin - offset
     }
 
-    pub fn get_ray(&self, s: Float, t: Float, rng: &mut RngGen) -> Ray {
-        let rd: Vec3 = self.lens_radius * Vec3::random_unit_xy(rng);
+    // Image coordinates the lens moves (s, t) to, by inverting the distortion
+    fn undistort(&self, s: Float, t: Float) -> (Float, Float) {
+        let [k1, k2, k3, p1, p2] = self.distortion;
+        if self.distortion.iter().all(|&c| c == 0.0) {
+            return (s, t)
+        }
+        // Coordinates at distance one from the lens, centered on its axis
+        let (shift_x, shift_y) = self.lens_shift;
+        let (width, height) = self.viewport;
+        let xd = (s - 0.5 + shift_x) * width;
+        let yd = (t - 0.5 + shift_y) * height;
+        // NOTE(srp): There's no closed form inverse, this converges quickly
+        // for the mild distortion of real lenses.
+        let (mut x, mut y) = (xd, yd);
+        for _ in 0..10 {
+            let r2 = x*x + y*y;
+            let radial = 1.0 + r2*(k1 + r2*(k2 + r2*k3));
+            let dx = 2.0*p1*x*y + p2*(r2 + 2.0*x*x);
+            let dy = p1*(r2 + 2.0*y*y) + 2.0*p2*x*y;
+            x = (xd - dx) / radial;
+            y = (yd - dy) / radial;
+        }
+        (x / width + 0.5 - shift_x, y / height + 0.5 - shift_y)
+    }
+
+    // Ray and how much it counts. None for points of the image the camera
+    // doesn't see, like the corners of a fisheye, or rays the lens blocks.
+    pub fn get_ray(&self, s: Float, t: Float, rng: &mut RngGen) -> Option<(Ray, Float)> {
+        let time = self.ray_time(t, rng);
+        match self.projection {
+            Projection::Perspective => if let Some(lens) = self.lens.as_ref() {
+                let (origin, direction, weight) = lens.sample(s, t, rng)?;
+                let origin = &self.origin + lens.mm_to_scene * self.view_to_world(origin.x(), origin.y(), origin.z());
+                let direction = self.view_to_world(direction.x(), direction.y(), direction.z());
+                return Some((Ray::new(&origin, &direction, time), weight))
+            },
+            Projection::Orthographic(_) => {
+                let origin = &self.lower_left_corner + s*&self.horizontal + t*&self.vertical;
+                return Some((Ray::new(&origin, &-&self.w, time), 1.0))
+            },
+            Projection::Equirectangular => {
+                let longitude = 2.0*PI * (s - 0.5);
+                let latitude = PI * (t - 0.5);
+                let direction = self.view_to_world(
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                    latitude.cos() * longitude.cos());
+                // ODS, eyes sit on a circle and look along its tangents
+                let eye = self.view_to_world(longitude.cos(), 0.0, -longitude.sin());
+                return Some((self.eye_ray(direction, eye, time), 1.0))
+            },
+            Projection::Fisheye(fov) => {
+                // The image circle touches the top and bottom of the image
+                let x = self.aspect_ratio * (2.0*s - 1.0);
+                let y = 2.0*t - 1.0;
+                let r = (x*x + y*y).sqrt();
+                if r > 1.0 {
+                    return None
+                }
+                let theta = r * 0.5 * fov.to_radians();
+                let (sin_phi, cos_phi) = if r > 0.0 { (y / r, x / r) } else { (0.0, 1.0) };
+                let direction = self.view_to_world(
+                    theta.sin() * cos_phi,
+                    theta.sin() * sin_phi,
+                    theta.cos());
+                return Some((self.eye_ray(direction, self.u.copy(), time), 1.0))
+            }
+        }
+
+        let (s, t) = self.undistort(s, t);
+        let rd: Vec3 = self.lens_radius * self.aperture_shape.sample(rng);
         let offset: Vec3 = &self.u*rd.x() + &self.v*rd.y();
-        Ray::new(
+        Some((Ray::new(
             &(&self.origin + &offset),
             &self.get_ray_dir(s, t, &offset),
-            rng.range(self.shutter_open_time, self.shutter_close_time))
+            time), 1.0))
+    }
+
+    // Ray from the eye, `eye_offset` along the unit `side` vector, that meets
+    // the center ray with `direction` at the convergence distance
+    fn eye_ray(&self, direction: Vec3, side: Vec3, time: Float) -> Ray {
+        let offset = self.eye_offset * side;
+        let direction = if self.convergence.is_finite() {
+            self.convergence * direction.unit_vector() - &offset
+        } else {
+            direction
+        };
+        Ray::new(&(&self.origin + &offset), &direction, time)
+    }
+
+    // Right, up and forward components to world space
+    fn view_to_world(&self, right: Float, up: Float, forward: Float) -> Vec3 {
+        right*&self.u + up*&self.v - forward*&self.w
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Evenly spread samples through the curve
+    fn samples(curve: &ShutterCurve, n: usize) -> Vec<Float> {
+        (0..n).map(|i| curve.sample((i as Float + 0.5) / n as Float)).collect()
     }
+
+    #[test]
+    fn box_shutter_is_uniform() {
+        for u in [0.0, 0.25, 0.5, 0.999] {
+            assert_eq!(ShutterCurve::Box.sample(u), u);
+        }
+    }
+
+    #[test]
+    fn trapezoid_samples_cover_the_exposure_in_order() {
+        let curve = ShutterCurve::Trapezoid(0.3, 0.2);
+        assert!(curve.sample(0.0).abs() < 1e-6);
+        assert!((curve.sample(1.0) - 1.0).abs() < 1e-6);
+        let times = samples(&curve, 1000);
+        assert!(times.iter().all(|t| (0.0..=1.0).contains(t)));
+        assert!(times.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn trapezoid_samples_follow_the_opening() {
+        // The first half of the opening ramp lets through a quarter of the
+        // light of the same time fully open
+        let curve = ShutterCurve::Trapezoid(0.4, 0.4);
+        let times = samples(&curve, 100_000);
+        let count = |t0: Float, t1: Float| times.iter().filter(|&&t| t >= t0 && t < t1).count() as Float;
+        let ramp = count(0.0, 0.2);
+        let open = count(0.4, 0.6);
+        assert!((ramp / open - 0.25).abs() < 0.01, "ramp {ramp}, open {open}");
+
+        let mean = times.iter().sum::<Float>() / times.len() as Float;
+        assert!((mean - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn efficiency_is_the_trapezoid_area() {
+        assert_eq!(ShutterCurve::Box.efficiency(), 1.0);
+        assert!((ShutterCurve::Trapezoid(0.3, 0.2).efficiency() - 0.75).abs() < 1e-6);
+        // Ramps that don't fit in the exposure get clamped
+        assert!((ShutterCurve::Trapezoid(0.8, 0.8).efficiency() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn lens_systems_build_without_thin_lens_settings() {
+        let mut builder = CameraBuilder::new();
+        builder.lens_system(LensSystem::double_gauss_50mm())
+            .stereo(0.065, 2.0, StereoLayout::SideBySide)
+            .distortion(0.1, 0.0, 0.0, 0.0, 0.0);
+        assert!(builder.stereo_layout().is_none());
+        assert_eq!(builder.build_views().len(), 1);
+    }
+
+
+    #[test]
+    fn rolling_shutter_stays_inside_the_shutter_window() {
+        let mut builder = CameraBuilder::new();
+        builder.shutter_open_time(0.0).shutter_close_time(1.0).rolling_shutter(0.98);
+        let camera = builder.build();
+        let mut rng = RngGen::new();
+        for _ in 0..1000 {
+            let row = rng.get();
+            assert!((0.0..=1.0).contains(&camera.ray_time(row, &mut rng)));
+        }
+        let bottom = (0..100).map(|_| camera.ray_time(0.0, &mut rng)).fold(0.0, Float::max);
+        assert!(bottom >= 0.98);
+        // A readout longer than the window leaves rows an instant to expose
+        builder.rolling_shutter(5.0);
+        let camera = builder.build();
+        assert!(camera.ray_time(0.0, &mut rng) <= 1.0);
+    }
+
 }
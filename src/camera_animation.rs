@@ -0,0 +1,236 @@
+use super::float::Float;
+use super::vec3::Vec3;
+type Point3 = Vec3;
+use super::camera::CameraBuilder;
+
+use std::ops::Range;
+
+#[derive(Clone, Copy)]
+pub enum Interpolation {
+    Linear,
+    CatmullRom, // Goes through every keyframe without sudden turns
+}
+
+// Camera placement at one point in scene time
+pub struct Keyframe {
+    time: Float,
+    lookfrom: Point3,
+    lookat: Point3,
+    vertical_fov: Float,
+    focus_dist: Float,
+}
+
+impl Keyframe {
+    pub fn new(time: Float, lookfrom: Point3, lookat: Point3,
+               vertical_fov: Float, focus_dist: Float) -> Self {
+        Self { time, lookfrom, lookat, vertical_fov, focus_dist }
+    }
+}
+
+// Camera path over scene time. Frame `n` opens the shutter at scene time
+// `n * frame_duration`, so animated objects keep moving from frame to frame.
+pub struct CameraAnimation {
+    keyframes: Vec<Keyframe>,
+    interpolation: Interpolation,
+    frame_duration: Float,
+    shutter: Float,
+    frames: Range<usize>,
+    time_range: (Float, Float),
+}
+
+impl CameraAnimation {
+    pub fn new(frame_duration: Float) -> Self {
+        Self {
+            keyframes: Vec::new(),
+            interpolation: Interpolation::Linear,
+            frame_duration,
+            shutter: 0.5,
+            frames: 0..1,
+            time_range: (0.0, 1.0),
+        }
+    }
+
+    // Keyframes can be added in any order
+    pub fn keyframe(&mut self, keyframe: Keyframe) -> &mut Self {
+        let index = self.keyframes.partition_point(|k| k.time <= keyframe.time);
+        self.keyframes.insert(index, keyframe);
+        self
+    }
+
+    pub fn interpolation(&mut self, interpolation: Interpolation) -> &mut Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    // Fraction of each frame the shutter stays open, 0.5 is a 180° shutter
+    pub fn shutter(&mut self, shutter: Float) -> &mut Self {
+        self.shutter = shutter.clamp(0.0, 1.0);
+        self
+    }
+
+    // Frames `first` up to, but not including, `end`
+    pub fn frames(&mut self, first: usize, end: usize) -> &mut Self {
+        self.frames = first..end;
+        self
+    }
+
+    // Scene times the BVHs and moving objects were built for
+    pub fn time_range(&mut self, time0: Float, time1: Float) -> &mut Self {
+        self.time_range = (time0, time1);
+        self
+    }
+
+    // NOTE(srp): BVHs only bound moving objects over the times they were
+    // built with, frames exposed outside of them would lose objects. Those
+    // get dropped, saying so.
+    pub fn frame_range(&self) -> Range<usize> {
+        let fits = |frame: &usize| {
+            let (open, close) = self.shutter_window(*frame);
+            open >= self.time_range.0 && close <= self.time_range.1
+        };
+        let first = self.frames.clone().find(fits);
+        let last = self.frames.clone().rev().find(fits);
+        let range = match (first, last) {
+            (Some(first), Some(last)) => first..last + 1,
+            _ => self.frames.start..self.frames.start
+        };
+        if range != self.frames {
+            eprintln!("Frames {:?} don't fit in the scene's time range {:?}, rendering frames {:?}",
+                      self.frames, self.time_range, range);
+        }
+        range
+    }
+
+    fn shutter_window(&self, frame: usize) -> (Float, Float) {
+        let shutter_open_time = frame as Float * self.frame_duration;
+        (shutter_open_time, shutter_open_time + self.shutter * self.frame_duration)
+    }
+
+    pub fn apply(&self, frame: usize, cam: &mut CameraBuilder) {
+        let (shutter_open_time, shutter_close_time) = self.shutter_window(frame);
+        cam.shutter_open_time(shutter_open_time)
+            .shutter_close_time(shutter_close_time);
+        if self.keyframes.is_empty() {
+            return
+        }
+
+        // The camera holds still while the shutter is open, placed where it
+        // is halfway through the exposure
+        let time = 0.5 * (shutter_open_time + shutter_close_time);
+        let point = |value: fn(&Keyframe) -> &Point3| Point3::new(
+            self.sample(time, |k| value(k).x()),
+            self.sample(time, |k| value(k).y()),
+            self.sample(time, |k| value(k).z()));
+        cam.lookfrom(point(|k| &k.lookfrom))
+            .lookat(point(|k| &k.lookat))
+            .vertical_fov(self.sample(time, |k| k.vertical_fov))
+            .focus_dist(self.sample(time, |k| k.focus_dist));
+    }
+
+    // Value of one keyframe parameter at `time`, held constant before the
+    // first keyframe and after the last one
+    fn sample(&self, time: Float, value: impl Fn(&Keyframe) -> Float) -> Float {
+        let keys = &self.keyframes;
+        let next = keys.partition_point(|k| k.time <= time);
+        if next == 0 {
+            return value(&keys[0])
+        }
+        if next == keys.len() {
+            return value(&keys[keys.len() - 1])
+        }
+        let (k1, k2) = (&keys[next - 1], &keys[next]);
+        let t = (time - k1.time) / (k2.time - k1.time);
+        let (p1, p2) = (value(k1), value(k2));
+        match self.interpolation {
+            Interpolation::Linear => p1 + t*(p2 - p1),
+            Interpolation::CatmullRom => {
+                // Ends get a phantom keyframe that repeats them
+                let p0 = if next >= 2 { value(&keys[next - 2]) } else { p1 };
+                let p3 = if next + 1 < keys.len() { value(&keys[next + 1]) } else { p2 };
+                0.5 * (2.0*p1
+                       + t*(p2 - p0)
+                       + t*t*(2.0*p0 - 5.0*p1 + 4.0*p2 - p3)
+                       + t*t*t*(3.0*p1 - p0 - 3.0*p2 + p3))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time: Float, fov: Float) -> Keyframe {
+        Keyframe::new(time, Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, -1.0), fov, 1.0)
+    }
+
+    fn fov_at(animation: &CameraAnimation, time: Float) -> Float {
+        animation.sample(time, |k| k.vertical_fov)
+    }
+
+    #[test]
+    fn linear_interpolates_between_keyframes() {
+        let mut animation = CameraAnimation::new(1.0);
+        animation.keyframe(key(0.0, 20.0)).keyframe(key(2.0, 60.0));
+        assert_eq!(fov_at(&animation, 0.5), 30.0);
+        assert_eq!(fov_at(&animation, 1.0), 40.0);
+    }
+
+    #[test]
+    fn holds_still_outside_the_keyframes() {
+        let mut animation = CameraAnimation::new(1.0);
+        animation.keyframe(key(1.0, 20.0)).keyframe(key(2.0, 60.0));
+        for interpolation in [Interpolation::Linear, Interpolation::CatmullRom] {
+            animation.interpolation(interpolation);
+            assert_eq!(fov_at(&animation, -5.0), 20.0);
+            assert_eq!(fov_at(&animation, 2.0), 60.0);
+            assert_eq!(fov_at(&animation, 9.0), 60.0);
+        }
+    }
+
+    #[test]
+    fn keyframes_are_sorted_by_time() {
+        let mut animation = CameraAnimation::new(1.0);
+        animation.keyframe(key(2.0, 60.0)).keyframe(key(0.0, 20.0)).keyframe(key(1.0, 30.0));
+        assert_eq!(fov_at(&animation, 0.5), 25.0);
+        assert_eq!(fov_at(&animation, 1.5), 45.0);
+    }
+
+    #[test]
+    fn catmull_rom_goes_through_keyframes() {
+        let mut animation = CameraAnimation::new(1.0);
+        animation.interpolation(Interpolation::CatmullRom)
+            .keyframe(key(0.0, 20.0)).keyframe(key(1.0, 50.0))
+            .keyframe(key(2.0, 30.0)).keyframe(key(3.0, 70.0));
+        for (time, fov) in [(0.0, 20.0), (1.0, 50.0), (2.0, 30.0), (3.0, 70.0)] {
+            assert!((fov_at(&animation, time) - fov).abs() < 1e-4);
+        }
+        // Smooth through the middle keyframes, unlike linear
+        let just_before = fov_at(&animation, 1.0 - 1e-3);
+        let just_after = fov_at(&animation, 1.0 + 1e-3);
+        assert!((just_after - just_before).abs() < 0.01);
+    }
+
+    #[test]
+    fn catmull_rom_keeps_straight_paths_straight() {
+        let mut animation = CameraAnimation::new(1.0);
+        animation.interpolation(Interpolation::CatmullRom)
+            .keyframe(key(0.0, 10.0)).keyframe(key(1.0, 20.0))
+            .keyframe(key(2.0, 30.0)).keyframe(key(3.0, 40.0));
+        assert!((fov_at(&animation, 1.25) - 22.5).abs() < 1e-4);
+        assert!((fov_at(&animation, 1.5) - 25.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn frames_outside_the_time_range_are_dropped() {
+        let mut animation = CameraAnimation::new(0.25);
+        animation.frames(0, 8).shutter(0.5);
+        // Frame 3 closes at 0.875, frame 4 would open at 1.0
+        assert_eq!(animation.frame_range(), 0..4);
+        animation.time_range(0.3, 2.0);
+        assert_eq!(animation.frame_range(), 2..8);
+        animation.time_range(5.0, 6.0);
+        assert!(animation.frame_range().is_empty());
+    }
+
+}
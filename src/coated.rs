@@ -0,0 +1,130 @@
+use super::float::Float;
+use super::vec3::Vec3;
+use super::rng_float::RngGen;
+use super::material::{Material, Scatter};
+use super::ray::Ray;
+use super::hittable::HitRecord;
+use super::color::Color;
+use super::onb::ONB;
+use super::microfacet::GGX;
+use super::fresnel::fresnel_dielectric;
+
+use std::rc::Rc;
+
+// Dielectric clearcoat over any base material. Light is reflected by the
+// coat with its Fresnel probability, otherwise it goes to the base and loses
+// the Fresnel reflection again on its way out. Light reflected back and forth
+// between the coat and the base is dropped.
+pub struct Coated {
+    base: Rc<dyn Material>,
+    ior: Float,
+    distribution: GGX,
+}
+
+impl Coated {
+    pub fn new(base: Rc<dyn Material>, ior: Float, roughness: Float) -> Self {
+        Self { base, ior, distribution: GGX::new(roughness) }
+    }
+
+    // Share of the light the coat lets through along a direction leaving the
+    // base, which is below the surface for bases that refract
+    fn transmitted(&self, cos_out: Float) -> Float {
+        let refraction_ratio = if cos_out < 0.0 { self.ior } else { self.ior.recip() };
+        1.0 - fresnel_dielectric(cos_out.abs(), refraction_ratio)
+    }
+}
+
+impl Material for Coated {
+    fn scatter(&self, r_in: &Ray, hit: &HitRecord, rng: &mut RngGen) -> Scatter {
+        if !hit.in_front_face() {
+            return self.base.scatter(r_in, hit, rng)
+        }
+
+        let frame = ONB::build_from_w(hit.normal());
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let refraction_ratio = self.ior.recip();
+
+        if fresnel_dielectric(wo.z(), refraction_ratio) > rng.get() {
+            let m = self.distribution.sample_visible_normal(&wo, rng.get(), rng.get());
+            let wi = (-&wo).reflect(&m);
+            if wi.z() <= 0.0 {
+                return Scatter::None
+            }
+            let weight = self.distribution.g2(&wo, &wi) / self.distribution.g1(&wo);
+            let new_ray = Ray::new(hit.p(), &frame.local_vec(&wi), r_in.time());
+            if self.distribution.is_smooth() {
+                return Scatter::Specular(new_ray, weight * Color::one())
+            }
+            let pdf = self.pdf(r_in, hit, &new_ray.direction().unit_vector());
+            return Scatter::Some(new_ray, weight * Color::one(), pdf)
+        }
+
+        let transmitted = |new_ray: &Ray| {
+            self.transmitted(Vec3::dot(&new_ray.direction().unit_vector(), hit.normal()))
+        };
+        match self.base.scatter(r_in, hit, rng) {
+            Scatter::Some(new_ray, attenuation, _) => {
+                let weight = transmitted(&new_ray);
+                let pdf = self.pdf(r_in, hit, &new_ray.direction().unit_vector());
+                Scatter::Some(new_ray, weight * attenuation, pdf)
+            },
+            Scatter::Specular(new_ray, attenuation) => {
+                let weight = transmitted(&new_ray);
+                Scatter::Specular(new_ray, weight * attenuation)
+            },
+            Scatter::None => Scatter::None
+        }
+    }
+
+    fn eval(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Color {
+        if !hit.in_front_face() {
+            return self.base.eval(r_in, hit, scattered)
+        }
+
+        let frame = ONB::build_from_w(hit.normal());
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let wi = frame.to_local(scattered);
+        let refraction_ratio = self.ior.recip();
+        let reflected = fresnel_dielectric(wo.z(), refraction_ratio);
+        let transmitted = self.transmitted(wi.z());
+
+        let mut f_cos = ((1.0 - reflected) * transmitted) * self.base.eval(r_in, hit, scattered);
+        if !self.distribution.is_smooth() {
+            if let Some((_, f, _)) = self.distribution.reflection(&wo, &wi) {
+                f_cos += (reflected * f) * Color::one();
+            }
+        }
+        f_cos
+    }
+
+    fn pdf(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Float {
+        if !hit.in_front_face() {
+            return self.base.pdf(r_in, hit, scattered)
+        }
+
+        let frame = ONB::build_from_w(hit.normal());
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let wi = frame.to_local(scattered);
+        let reflected = fresnel_dielectric(wo.z(), self.ior.recip());
+
+        let mut pdf = (1.0 - reflected) * self.base.pdf(r_in, hit, scattered);
+        if !self.distribution.is_smooth() {
+            if let Some((_, _, p)) = self.distribution.reflection(&wo, &wi) {
+                pdf += reflected * p;
+            }
+        }
+        pdf
+    }
+
+    fn emmited(&self, r_in: &Ray, hit: &HitRecord) -> Color {
+        self.base.emmited(r_in, hit)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.base.is_emissive()
+    }
+
+    fn has_non_specular(&self) -> bool {
+        self.base.has_non_specular() || !self.distribution.is_smooth()
+    }
+}
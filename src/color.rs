@@ -2,6 +2,11 @@ pub use super::float::*;
 
 pub type Color = super::Vec3;
 
+// NOTE(srp): Radiance for a luminance of one cd/m^2. Lights given in
+// photometric units (candela, lumens) all go through this so they agree with
+// each other and with the physical sky.
+pub const PHOTOMETRIC_SCALE: Float = 3e-5;
+
 impl Color {
     pub fn r(&self, scale: Float) -> i32 {
         Color::to_color(self.x(), scale)
@@ -0,0 +1,98 @@
+use super::float::Float;
+use super::rng_float::RngGen;
+use super::material::{Material, Scatter};
+use super::ray::Ray;
+use super::hittable::HitRecord;
+use super::vec3::Vec3;
+use super::color::Color;
+use super::onb::ONB;
+use super::microfacet::GGX;
+use super::fresnel::fresnel_conductor;
+
+// Rough metal with a GGX microfacet distribution and a complex index of
+// refraction (eta + i*k) per color channel.
+pub struct Conductor {
+    eta: Color,
+    k: Color,
+    distribution: GGX,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: Float) -> Self {
+        Self { eta, k, distribution: GGX::new(roughness) }
+    }
+
+    pub fn gold(roughness: Float) -> Self {
+        Self::new(Color::new(0.143, 0.374, 1.442), Color::new(3.983, 2.386, 1.603), roughness)
+    }
+
+    pub fn silver(roughness: Float) -> Self {
+        Self::new(Color::new(0.155, 0.117, 0.138), Color::new(4.828, 3.122, 2.147), roughness)
+    }
+
+    pub fn copper(roughness: Float) -> Self {
+        Self::new(Color::new(0.200, 0.924, 1.102), Color::new(3.912, 2.452, 2.142), roughness)
+    }
+
+    pub fn aluminium(roughness: Float) -> Self {
+        Self::new(Color::new(1.657, 0.880, 0.521), Color::new(9.224, 6.270, 4.837), roughness)
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, r_in: &Ray, hit: &HitRecord, rng: &mut RngGen) -> Scatter {
+        let frame = ONB::build_from_w(hit.normal());
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        if wo.z() <= 0.0 {
+            return Scatter::None
+        }
+
+        let m = self.distribution.sample_visible_normal(&wo, rng.get(), rng.get());
+        let wi = (-&wo).reflect(&m);
+        if wi.z() <= 0.0 {
+            return Scatter::None
+        }
+
+        // NOTE(srp): Sampling visible normals cancels D and most of G, the
+        // weight f*cos/pdf is just F * G2/G1.
+        let fresnel = fresnel_conductor(Vec3::dot(&wo, &m), &self.eta, &self.k);
+        let weight = self.distribution.g2(&wo, &wi) / self.distribution.g1(&wo);
+        let new_ray = Ray::new(hit.p(), &frame.local_vec(&wi), r_in.time());
+        if self.distribution.is_smooth() {
+            Scatter::Specular(new_ray, weight * fresnel)
+        } else {
+            let pdf = self.pdf(r_in, hit, &new_ray.direction().unit_vector());
+            Scatter::Some(new_ray, weight * fresnel, pdf)
+        }
+    }
+
+    fn eval(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Color {
+        if self.distribution.is_smooth() {
+            return Color::zero()
+        }
+        let frame = ONB::build_from_w(hit.normal());
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let wi = frame.to_local(scattered);
+        match self.distribution.reflection(&wo, &wi) {
+            Some((m, f_cos, _)) => f_cos * fresnel_conductor(Vec3::dot(&wo, &m), &self.eta, &self.k),
+            None => Color::zero()
+        }
+    }
+
+    fn pdf(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Float {
+        if self.distribution.is_smooth() {
+            return 0.0
+        }
+        let frame = ONB::build_from_w(hit.normal());
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let wi = frame.to_local(scattered);
+        match self.distribution.reflection(&wo, &wi) {
+            Some((_, _, pdf)) => pdf,
+            None => 0.0
+        }
+    }
+
+    fn has_non_specular(&self) -> bool {
+        !self.distribution.is_smooth()
+    }
+}
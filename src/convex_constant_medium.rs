@@ -4,6 +4,7 @@ use super::vec3::Vec3;
 use super::color::Color;
 use super::ray::Ray;
 use super::hittable::{Hittable, HitRecord};
+use super::hittable_list::HittableList;
 use super::texture::Texture;
 use super::material::Material;
 use super::aabb::AABB;
@@ -32,6 +33,11 @@ impl Hittable for ConvexConstantMedium {
         self.boundary.bounding_box(time0, time1)
     }
 
+    // NOTE(srp): The boundary only gives the medium its shape, rays never
+    // hit its surfaces. Lights in it aren't part of the scene, so none are
+    // handed on.
+    fn collect_lights(&self, _lights: &mut HittableList) {}
+
     fn hit(&self, r:&Ray, t_min:Float, t_max:Float, rng: &mut RngGen) -> Option<HitRecord> {
         // Print occasional samples when debugging. To enable, set enableDebug.
         const ENABLE_DEBUG: bool = false;
@@ -0,0 +1,252 @@
+use super::float::{Float, SQRT_2};
+use super::rng_float::RngGen;
+use super::vec3::Vec3;
+type Point3 = Vec3;
+use super::ray::Ray;
+use super::hittable::{Hittable, HitRecord};
+use super::material::Material;
+use super::aabb::AABB;
+use super::onb::ONB;
+
+use std::rc::Rc;
+
+#[derive(Clone, Copy)]
+pub enum CurveType {
+    Ribbon,   // Flat strip, always facing the incoming ray
+    Cylinder, // Flat strip, shaded as if it were a round tube
+}
+
+// Cubic Bézier curve whose width varies linearly from root (u = 0) to tip
+// (u = 1). A Curve only spans [u_min, u_max] of the full curve, so long
+// strands can be split into segments with tighter bounding boxes.
+pub struct Curve {
+    cp: [Point3; 4], // Control points of this segment only
+    width0: Float,
+    width1: Float,
+    u_min: Float,
+    u_max: Float,
+    curve_type: CurveType,
+    mat: Rc<dyn Material>,
+}
+
+impl Curve {
+    pub fn new(cp: [Point3; 4], width0: Float, width1: Float,
+               curve_type: CurveType, mat: Rc<dyn Material>) -> Self {
+        Self::new_segment(&cp, width0, width1, 0.0, 1.0, curve_type, mat)
+    }
+
+    // Splits the full curve into `segments` pieces, ready to be added to a
+    // HittableList and put into a BVH.
+    pub fn new_segments(cp: [Point3; 4], width0: Float, width1: Float,
+                        curve_type: CurveType, mat: Rc<dyn Material>,
+                        segments: usize) -> Vec<Rc<dyn Hittable>> {
+        let segments = segments.max(1);
+        let mut out: Vec<Rc<dyn Hittable>> = Vec::with_capacity(segments);
+        for i in 0..segments {
+            let u_min = i as Float / segments as Float;
+            let u_max = (i+1) as Float / segments as Float;
+            out.push(Rc::new(Self::new_segment(
+                        &cp, width0, width1, u_min, u_max, curve_type, mat.clone())));
+        }
+        out
+    }
+
+    fn new_segment(full_cp: &[Point3; 4], width0: Float, width1: Float,
+                   u_min: Float, u_max: Float,
+                   curve_type: CurveType, mat: Rc<dyn Material>) -> Self {
+        let cp = [
+            blossom_bezier(full_cp, u_min, u_min, u_min),
+            blossom_bezier(full_cp, u_min, u_min, u_max),
+            blossom_bezier(full_cp, u_min, u_max, u_max),
+            blossom_bezier(full_cp, u_max, u_max, u_max),
+        ];
+        Self { cp, width0, width1, u_min, u_max, curve_type, mat }
+    }
+
+    fn width_at(&self, u: Float) -> Float {
+        (1.0 - u)*self.width0 + u*self.width1
+    }
+
+    fn max_width(&self, u0: Float, u1: Float) -> Float {
+        self.width_at(u0).max(self.width_at(u1))
+    }
+
+    // NOTE(srp): `cp` is in ray space (ray starts at the origin and travels
+    // along +z), `u0` and `u1` are parameters of the full curve.
+    // Returns the ray space depth of the hit and its (u, v).
+    fn recursive_intersect(&self, cp: &[Point3; 4], u0: Float, u1: Float,
+                           depth: i32, z_min: Float, z_max: Float)
+            -> Option<(Float, Float, Float)> {
+        if depth > 0 {
+            let split = subdivide_bezier(cp);
+            let u = [u0, 0.5*(u0 + u1), u1];
+            let mut z_max = z_max;
+            let mut closest = None;
+            for seg in 0..2 {
+                let seg_cp = [
+                    split[3*seg].copy(),
+                    split[3*seg + 1].copy(),
+                    split[3*seg + 2].copy(),
+                    split[3*seg + 3].copy(),
+                ];
+                let half_width = 0.5*self.max_width(u[seg], u[seg + 1]);
+                if !overlaps_ray(&seg_cp, half_width, z_min, z_max) {
+                    continue;
+                }
+                if let Some(hit) = self.recursive_intersect(
+                        &seg_cp, u[seg], u[seg + 1], depth - 1, z_min, z_max) {
+                    z_max = hit.0;
+                    closest = Some(hit);
+                }
+            }
+            return closest
+        }
+
+        // Reject hits past the perpendiculars at both ends of the segment
+        let edge = (cp[1].y() - cp[0].y()) * -cp[0].y() + cp[0].x() * (cp[0].x() - cp[1].x());
+        if edge < 0.0 { return None }
+        let edge = (cp[2].y() - cp[3].y()) * -cp[3].y() + cp[3].x() * (cp[3].x() - cp[2].x());
+        if edge < 0.0 { return None }
+
+        // Closest point on the segment's chord to the ray
+        let seg_x = cp[3].x() - cp[0].x();
+        let seg_y = cp[3].y() - cp[0].y();
+        let denom = seg_x*seg_x + seg_y*seg_y;
+        if denom == 0.0 { return None }
+        let w = (-cp[0].x()*seg_x - cp[0].y()*seg_y) / denom;
+
+        let u = (u0 + w*(u1 - u0)).clamp(u0, u1);
+        let hit_width = self.width_at(u);
+        let (pc, dpcdw) = eval_bezier(cp, w.clamp(0.0, 1.0));
+        let dist2 = pc.x()*pc.x() + pc.y()*pc.y();
+        if dist2 > hit_width*hit_width*0.25 { return None }
+        if pc.z() < z_min || pc.z() > z_max { return None }
+
+        let dist = dist2.sqrt();
+        let edge_func = dpcdw.x() * -pc.y() + pc.x() * dpcdw.y();
+        let v = if edge_func > 0.0 {
+            0.5 + dist/hit_width
+        } else {
+            0.5 - dist/hit_width
+        };
+        Some((pc.z(), u, v))
+    }
+}
+
+impl Hittable for Curve {
+    fn hit(&self, r:&Ray, t_min:Float, t_max:Float, _rng: &mut RngGen) -> Option<HitRecord> {
+        let dir_len = r.direction().length();
+        let frame = ONB::build_from_w(r.direction());
+        let cp: [Point3; 4] = std::array::from_fn(|i| frame.to_local(&(&self.cp[i] - r.origin())));
+
+        let z_min = t_min * dir_len;
+        let z_max = t_max * dir_len;
+        let half_width = 0.5*self.max_width(self.u_min, self.u_max);
+        if !overlaps_ray(&cp, half_width, z_min, z_max) {
+            return None
+        }
+
+        // Subdivide until the segments are close enough to straight lines
+        let mut l0: Float = 0.0;
+        for i in 0..2 {
+            let d = &cp[i] - 2.0*&cp[i + 1] + &cp[i + 2];
+            l0 = l0.max(d.x().abs()).max(d.y().abs()).max(d.z().abs());
+        }
+        let eps = 0.05*self.max_width(self.u_min, self.u_max);
+        let depth = if l0 > 0.0 && eps > 0.0 {
+            ((SQRT_2 * 6.0 * l0 / (8.0 * eps)).log2().floor() as i32 / 2).clamp(0, 10)
+        } else {
+            0
+        };
+
+        let (z, u, v) = self.recursive_intersect(&cp, self.u_min, self.u_max, depth, z_min, z_max)?;
+        let t = z / dir_len;
+
+        // Shading frame in world space
+        let local_u = (u - self.u_min) / (self.u_max - self.u_min);
+        let (axis_p, dpdu) = eval_bezier(&self.cp, local_u);
+        let across = Vec3::cross(r.direction(), &dpdu);
+        if across.near_zero() {
+            return None
+        }
+        let across = across.unit_vector();
+        let facing = Vec3::cross(&across, &dpdu).unit_vector();
+
+        let outward_normal = match self.curve_type {
+            CurveType::Ribbon => facing,
+            CurveType::Cylinder => {
+                let half_width = 0.5*self.width_at(u);
+                let s = (Vec3::dot(&(r.at(t) - &axis_p), &across) / half_width).clamp(-1.0, 1.0);
+                (s*&across + (1.0 - s*s).sqrt()*&facing).unit_vector()
+            }
+        };
+
+        let dpdv = self.width_at(u) * &across;
+        let dpdu = dpdu / (self.u_max - self.u_min);
+        let mut rec = HitRecord::new(r, outward_normal, self.mat.clone(), t, u, v);
+        rec.set_tangents(dpdu, dpdv);
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _time0: Float, _time1: Float) -> Option<AABB> {
+        let half_width = 0.5*self.max_width(self.u_min, self.u_max);
+        let mut min = self.cp[0].copy();
+        let mut max = self.cp[0].copy();
+        for p in self.cp.iter().skip(1) {
+            min.set_to_min(p);
+            max.set_to_max(p);
+        }
+        let expand = Vec3::new(half_width, half_width, half_width);
+        Some(AABB::new(min - &expand, max + &expand))
+    }
+}
+
+fn overlaps_ray(cp: &[Point3; 4], half_width: Float, z_min: Float, z_max: Float) -> bool {
+    let mut min = cp[0].copy();
+    let mut max = cp[0].copy();
+    for p in cp.iter().skip(1) {
+        min.set_to_min(p);
+        max.set_to_max(p);
+    }
+    min.x() - half_width <= 0.0 && max.x() + half_width >= 0.0 &&
+    min.y() - half_width <= 0.0 && max.y() + half_width >= 0.0 &&
+    min.z() - half_width <= z_max && max.z() + half_width >= z_min
+}
+
+fn blossom_bezier(p: &[Point3; 4], u0: Float, u1: Float, u2: Float) -> Point3 {
+    let a = [
+        Vec3::lerp(&p[0], &p[1], u0),
+        Vec3::lerp(&p[1], &p[2], u0),
+        Vec3::lerp(&p[2], &p[3], u0),
+    ];
+    let b = [Vec3::lerp(&a[0], &a[1], u1), Vec3::lerp(&a[1], &a[2], u1)];
+    Vec3::lerp(&b[0], &b[1], u2)
+}
+
+fn subdivide_bezier(cp: &[Point3; 4]) -> [Point3; 7] {
+    [
+        cp[0].copy(),
+        (&cp[0] + &cp[1]) / 2.0,
+        (&cp[0] + 2.0*&cp[1] + &cp[2]) / 4.0,
+        (&cp[0] + 3.0*&cp[1] + 3.0*&cp[2] + &cp[3]) / 8.0,
+        (&cp[1] + 2.0*&cp[2] + &cp[3]) / 4.0,
+        (&cp[2] + &cp[3]) / 2.0,
+        cp[3].copy(),
+    ]
+}
+
+// Returns the point at `u` and the derivative with respect to `u`
+fn eval_bezier(cp: &[Point3; 4], u: Float) -> (Point3, Vec3) {
+    let cp1 = [
+        Vec3::lerp(&cp[0], &cp[1], u),
+        Vec3::lerp(&cp[1], &cp[2], u),
+        Vec3::lerp(&cp[2], &cp[3], u),
+    ];
+    let cp2 = [Vec3::lerp(&cp1[0], &cp1[1], u), Vec3::lerp(&cp1[1], &cp1[2], u)];
+    let deriv = if (&cp2[1] - &cp2[0]).length_squared() > 0.0 {
+        3.0*(&cp2[1] - &cp2[0])
+    } else {
+        &cp[3] - &cp[0]
+    };
+    (Vec3::lerp(&cp2[0], &cp2[1], u), deriv)
+}
@@ -1,4 +1,4 @@
-use super::float::Float;
+use super::float::{Float, SQRT_2};
 use super::vec3::Vec3;
 type Point3 = Vec3;
 use super::color::Color;
@@ -14,7 +14,9 @@ use super::dielectric::Dielectric;
 use super::sphere::Sphere;
 use super::moving_sphere::MovingSphere;
 use super::bvh::BVH;
-use super::camera::CameraBuilder;
+use super::camera::{CameraBuilder, Projection, StereoLayout, PhysicalCamera, ShutterCurve};
+use super::aperture::Aperture;
+use super::lens_system::LensSystem;
 use super::noise_texture::NoiseTexture;
 use super::image_texture::ImageTexture;
 use super::renderer::Sky;
@@ -23,7 +25,20 @@ use super::aarect::{XyRect, YzRect, ZxRect};
 use super::rect_prism::RectPrism;
 use super::translate::Translate;
 use super::rotate_y::RotateY;
+use super::spin_z::SpinZ;
 use super::convex_constant_medium::ConvexConstantMedium;
+use super::curve::{Curve, CurveType};
+use super::conductor::Conductor;
+use super::principled::PrincipledBuilder;
+use super::texture::Texture;
+use super::mix_material::MixMaterial;
+use super::coated::Coated;
+use super::normal_map::BumpMap;
+use super::environment_map::EnvironmentMap;
+use super::physical_sky::PhysicalSky;
+use super::delta_light::{DeltaLight, PointLight, SpotLight, DirectionalLight};
+use super::ies::{IesProfile, IesLight};
+use super::camera_animation::{CameraAnimation, Keyframe, Interpolation};
 
 use std::path::Path;
 use std::rc::Rc;
@@ -37,6 +52,24 @@ pub enum DefaultScene {
     CornellBox,
     CornellSmoke,
     FinalSceneBook2,
+    Grass,
+    Conductors,
+    Glass,
+    Principled,
+    Layered,
+    Bumps,
+    Environment,
+    Daylight,
+    DeltaLights,
+    Luminaires,
+    Isometric,
+    Stereo,
+    Bokeh,
+    Photographic,
+    DoubleGauss,
+    TiltShift,
+    Flythrough,
+    RollingShutter,
 }
 
 pub fn select_default_scene(scene: &DefaultScene, rng: &mut RngGen) -> HittableList {
@@ -49,6 +82,24 @@ pub fn select_default_scene(scene: &DefaultScene, rng: &mut RngGen) -> HittableL
         DefaultScene::CornellBox => cornell_box(),
         DefaultScene::CornellSmoke => cornell_smoke(),
         DefaultScene::FinalSceneBook2 => final_scene_book2(rng),
+        DefaultScene::Grass => grass(rng),
+        DefaultScene::Conductors => conductors(),
+        DefaultScene::Glass => glass(),
+        DefaultScene::Principled => principled(rng),
+        DefaultScene::Layered => layered(rng),
+        DefaultScene::Bumps => bumps(rng),
+        DefaultScene::Environment => environment(),
+        DefaultScene::Daylight => environment(),
+        DefaultScene::DeltaLights => delta_lights(),
+        DefaultScene::Luminaires => luminaires(),
+        DefaultScene::Isometric => random_scene(rng),
+        DefaultScene::Stereo => conductors(),
+        DefaultScene::Bokeh => bokeh(rng),
+        DefaultScene::Photographic => environment(),
+        DefaultScene::DoubleGauss => conductors(),
+        DefaultScene::TiltShift => random_scene(rng),
+        DefaultScene::Flythrough => random_scene(rng),
+        DefaultScene::RollingShutter => rolling_shutter(),
     }
 }
 
@@ -62,22 +113,89 @@ pub fn select_default_scene_cam_settings(scene: &DefaultScene) -> CameraBuilder
         DefaultScene::CornellBox => cornell_box_cam(),
         DefaultScene::CornellSmoke => cornell_box_cam(),
         DefaultScene::FinalSceneBook2 => final_scene_book2_cam(),
+        DefaultScene::Grass => grass_cam(),
+        DefaultScene::Conductors => conductors_cam(),
+        DefaultScene::Glass => conductors_cam(),
+        DefaultScene::Principled => principled_cam(),
+        DefaultScene::Layered => conductors_cam(),
+        DefaultScene::Bumps => conductors_cam(),
+        DefaultScene::Environment => conductors_cam(),
+        DefaultScene::Daylight => conductors_cam(),
+        DefaultScene::DeltaLights => conductors_cam(),
+        DefaultScene::Luminaires => conductors_cam(),
+        DefaultScene::Isometric => isometric_cam(),
+        DefaultScene::Stereo => stereo_cam(),
+        DefaultScene::Bokeh => bokeh_cam(),
+        DefaultScene::Photographic => photographic_cam(),
+        DefaultScene::DoubleGauss => double_gauss_cam(),
+        DefaultScene::TiltShift => tilt_shift_cam(),
+        DefaultScene::Flythrough => random_scene_cam(),
+        DefaultScene::RollingShutter => rolling_shutter_cam(),
     }
 }
 
 pub fn select_default_scene_sky(scene: &DefaultScene) -> Sky {
     match scene {
         DefaultScene::RandomScene
+        | DefaultScene::Isometric
+        | DefaultScene::TiltShift
+        | DefaultScene::Flythrough
+        | DefaultScene::RollingShutter
         | DefaultScene::TwoSpheres
         | DefaultScene::Earth
-        | DefaultScene::PerlinSpheres =>
+        | DefaultScene::PerlinSpheres
+        | DefaultScene::Grass
+        | DefaultScene::Conductors
+        | DefaultScene::Stereo
+        | DefaultScene::DoubleGauss
+        | DefaultScene::Glass
+        | DefaultScene::Principled
+        | DefaultScene::Layered
+        | DefaultScene::Bumps =>
             Sky::Gradient(Color::one(), Color::new(0.5, 0.7, 1.0)),
 
         DefaultScene::SimpleLight
         | DefaultScene::FinalSceneBook2
         | DefaultScene::CornellSmoke
-        | DefaultScene::CornellBox =>
+        | DefaultScene::CornellBox
+        | DefaultScene::DeltaLights
+        | DefaultScene::Luminaires =>
             Sky::SolidColor(Color::zero()),
+
+        DefaultScene::Bokeh =>
+            Sky::Gradient(Color::new(0.02, 0.02, 0.03), Color::new(0.1, 0.12, 0.2)),
+
+        // NOTE(srp): Any equirectangular .hdr works, it isn't in the repo
+        DefaultScene::Environment =>
+            Sky::EnvironmentMap(EnvironmentMap::new(Path::new("environment.hdr"), 0.0, 1.0)),
+
+        DefaultScene::Daylight
+        | DefaultScene::Photographic =>
+            Sky::Physical(PhysicalSky::new(25.0, 135.0, 3.0, Color::new(0.3, 0.3, 0.3), 1.0)),
+    }
+}
+
+pub fn select_default_scene_lights(scene: &DefaultScene) -> Vec<Rc<dyn DeltaLight>> {
+    match scene {
+        DefaultScene::DeltaLights => delta_lights_lights(),
+        DefaultScene::Luminaires => luminaires_lights(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn select_default_scene_light_scale(scene: &DefaultScene) -> Float {
+    match scene {
+        // NOTE(srp): Indoor light levels are far below daylight
+        DefaultScene::Luminaires => 2000.0,
+        _ => 1.0,
+    }
+}
+
+// None for scenes that render a single image
+pub fn select_default_scene_animation(scene: &DefaultScene) -> Option<CameraAnimation> {
+    match scene {
+        DefaultScene::Flythrough => Some(flythrough_animation()),
+        _ => None,
     }
 }
 
@@ -159,6 +277,105 @@ fn random_scene_cam() -> CameraBuilder {
     cam
 }
 
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 100;
+    const MAX_DEPTH: i32 = 50;
+ * */
+// Wide open, with the plane of focus laid along the ground
+fn tilt_shift_cam() -> CameraBuilder {
+    let mut cam = random_scene_cam();
+    cam.aperture(1.0)
+        .tilt(80.0, 0.0);
+    cam
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 1.0;
+    const IMAGE_WIDTH:usize = 300;
+    const SAMPLES_PER_PIXEL:i32 = 100;
+    const MAX_DEPTH: i32 = 12;
+ * */
+// Propeller filmed by a phone-like sensor, the blades bend because each row
+// sees them at a different angle
+fn rolling_shutter() -> HittableList {
+    let mut objects = HittableList::new();
+    let ground_mat = Rc::new(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5)));
+    objects.add(Rc::new(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_mat)));
+
+    let blade_mat = Rc::new(Lambertian::new_from_color(Color::new(0.8, 0.15, 0.1)));
+    let hub_mat = Rc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.2));
+    let mut propeller = HittableList::new();
+    propeller.add(Rc::new(RectPrism::new(
+                &Point3::new(-2.0, -0.12, -0.05), &Point3::new(2.0, 0.12, 0.05), blade_mat.clone())));
+    propeller.add(Rc::new(RectPrism::new(
+                &Point3::new(-0.12, -2.0, -0.05), &Point3::new(0.12, 2.0, 0.05), blade_mat)));
+    propeller.add(Rc::new(Sphere::new(Point3::zero(), 0.3, hub_mat)));
+    let spinning = Rc::new(SpinZ::new(Rc::new(propeller), 1.5, 0.0, 1.0));
+    objects.add(Rc::new(Translate::new(spinning, Vec3::new(0.0, 2.5, 0.0))));
+
+    objects
+}
+
+fn rolling_shutter_cam() -> CameraBuilder {
+    let mut cam = CameraBuilder::new();
+    cam.lookfrom(Point3::new(0.0, 2.5, 10.0))
+        .lookat(Point3::new(0.0, 2.5, 0.0))
+        .vertical_fov(30.0)
+        .aperture(0.0)
+        .aspect_ratio(1.0)
+        .shutter_open_time(0.0)
+        .shutter_close_time(1.0)
+        .shutter_curve(ShutterCurve::Trapezoid(0.3, 0.3))
+        // Each row is exposed for 0.02
+        .rolling_shutter(0.98);
+    cam
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 50;
+    const MAX_DEPTH: i32 = 50;
+ * */
+// One second at 24 frames per second, swinging around the random scene
+// while the bouncing balls rise
+fn flythrough_animation() -> CameraAnimation {
+    let mut animation = CameraAnimation::new(1.0 / 24.0);
+    animation.frames(0, 24)
+        .shutter(0.5)
+        .interpolation(Interpolation::CatmullRom)
+        .keyframe(Keyframe::new(0.0, Point3::new(12.0, 2.0, 3.0), Point3::zero(), 20.0, 10.0))
+        .keyframe(Keyframe::new(0.5, Point3::new(9.0, 3.0, 8.0), Point3::new(0.0, 0.5, 0.0), 25.0, 10.0))
+        .keyframe(Keyframe::new(1.0, Point3::new(3.0, 2.0, 12.0), Point3::new(0.0, 1.0, 0.0), 30.0, 11.0));
+    animation
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 50;
+    const MAX_DEPTH: i32 = 50;
+ * */
+// Random scene seen from the true isometric angles
+fn isometric_cam() -> CameraBuilder {
+    let elevation = (1.0 as Float / SQRT_2).atan();
+    let lookfrom = 30.0 * Vec3::new(elevation.cos() / SQRT_2, elevation.sin(), elevation.cos() / SQRT_2);
+    let mut cam = CameraBuilder::new();
+    cam.lookfrom(lookfrom)
+        .lookat(Vec3::zero())
+        .projection(Projection::Orthographic(12.0))
+        .aspect_ratio(16.0 / 9.0)
+        .shutter_open_time(0.0)
+        .shutter_close_time(1.0);
+    cam
+}
+
 
 /* Previous settings:
  * IMAGE
@@ -437,3 +654,422 @@ fn final_scene_book2_cam() -> CameraBuilder {
         .shutter_close_time(1.0);
     cam
 }
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 100;
+    const MAX_DEPTH: i32 = 12;
+ * */
+fn grass(rng: &mut RngGen) -> HittableList {
+    let mut objects = HittableList::new();
+    let ground_mat = Rc::new(Lambertian::new_from_color(Color::new(0.35, 0.25, 0.15)));
+    objects.add(Rc::new(Sphere::new(Point3::new(0.0,-1000.0,0.0), 1000.0, ground_mat)));
+
+    let mut blades = HittableList::new();
+    let blade_mat = Rc::new(Lambertian::new_from_color(Color::new(0.2, 0.5, 0.1)));
+    const BLADES: i32 = 4000;
+    const SEGMENTS: usize = 2;
+    for i in 0..BLADES {
+        let root = Point3::new(rng.range(-4.0, 4.0), 0.0, rng.range(-4.0, 4.0));
+        let height = rng.range(0.4, 0.8);
+        let lean = Vec3::new(rng.range(-0.3, 0.3), 0.0, rng.range(-0.3, 0.3));
+        let cp = [
+            root.copy(),
+            &root + Vec3::new(0.0, 0.4*height, 0.0),
+            &root + 0.5*&lean + Vec3::new(0.0, 0.8*height, 0.0),
+            &root + &lean + Vec3::new(0.0, height, 0.0),
+        ];
+        let curve_type = if i % 2 == 0 { CurveType::Ribbon } else { CurveType::Cylinder };
+        let segments = Curve::new_segments(cp, 0.03, 0.002, curve_type, blade_mat.clone(), SEGMENTS);
+        for segment in segments {
+            blades.add(segment);
+        }
+    }
+    objects.add(Rc::new(BVH::new(&mut blades, 0.0, 1.0, rng)));
+
+    objects
+}
+
+fn grass_cam() -> CameraBuilder {
+    let mut cam = CameraBuilder::new();
+    cam.lookfrom(Point3::new(0.0, 1.2, 6.0))
+        .lookat(Point3::new(0.0, 0.3, 0.0))
+        .vertical_fov(30.0)
+        .aperture(0.0)
+        .aspect_ratio(16.0 / 9.0);
+    cam
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 100;
+    const MAX_DEPTH: i32 = 12;
+ * */
+fn conductors() -> HittableList {
+    let mut objects = HittableList::new();
+    let checker = Rc::new(CheckerTexture::new_solid(Color::new(0.2,0.3,0.1), Color::new(0.9,0.9,0.9)));
+    let ground_mat = Rc::new(Lambertian::new(checker));
+    objects.add(Rc::new(Sphere::new(Point3::new(0.0,-1000.0,0.0), 1000.0, ground_mat)));
+
+    let metals: [Rc<dyn Material>; 4] = [
+        Rc::new(Conductor::gold(0.2)),
+        Rc::new(Conductor::silver(0.0)),
+        Rc::new(Conductor::copper(0.4)),
+        Rc::new(Conductor::aluminium(0.7)),
+    ];
+    for (i, mat) in metals.into_iter().enumerate() {
+        let center = Point3::new(-3.3 + 2.2*i as Float, 1.0, 0.0);
+        objects.add(Rc::new(Sphere::new(center, 1.0, mat)));
+    }
+
+    objects
+}
+
+fn conductors_cam() -> CameraBuilder {
+    let mut cam = CameraBuilder::new();
+    cam.lookfrom(Point3::new(0.0, 2.0, 10.0))
+        .lookat(Point3::new(0.0, 1.0, 0.0))
+        .vertical_fov(30.0)
+        .aperture(0.0)
+        .aspect_ratio(16.0 / 9.0);
+    cam
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 32.0 / 9.0;
+    const IMAGE_WIDTH:usize = 800;
+    const SAMPLES_PER_PIXEL:i32 = 100;
+    const MAX_DEPTH: i32 = 50;
+ * */
+// Side by side pair for VR or parallel viewing, the spheres sit on the screen
+fn stereo_cam() -> CameraBuilder {
+    let mut cam = conductors_cam();
+    cam.stereo(0.3, 10.0, StereoLayout::SideBySide);
+    cam
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 200;
+    const MAX_DEPTH: i32 = 16;
+ * */
+fn glass() -> HittableList {
+    let mut objects = HittableList::new();
+    let checker = Rc::new(CheckerTexture::new_solid(Color::new(0.2,0.3,0.1), Color::new(0.9,0.9,0.9)));
+    let ground_mat = Rc::new(Lambertian::new(checker));
+    objects.add(Rc::new(Sphere::new(Point3::new(0.0,-1000.0,0.0), 1000.0, ground_mat)));
+    let back_mat = Rc::new(Lambertian::new_from_color(Color::new(0.7, 0.2, 0.1)));
+    objects.add(Rc::new(XyRect::new(-8.0, 8.0, 0.0, 4.0, -3.0, back_mat)));
+
+    let glasses: [Rc<dyn Material>; 4] = [
+        Rc::new(Dielectric::new(1.5)),
+        Rc::new(Dielectric::new_rough(1.5, 0.1)),
+        Rc::new(Dielectric::new_rough(1.5, 0.3)),
+        Rc::new(Dielectric::new_absorbing(1.5, 0.0, Color::new(0.2, 0.6, 0.9), 1.0)),
+    ];
+    for (i, mat) in glasses.into_iter().enumerate() {
+        let center = Point3::new(-3.3 + 2.2*i as Float, 1.0, 0.0);
+        objects.add(Rc::new(Sphere::new(center, 1.0, mat)));
+    }
+
+    objects
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 200;
+    const MAX_DEPTH: i32 = 16;
+ * */
+fn principled(rng: &mut RngGen) -> HittableList {
+    let mut objects = HittableList::new();
+    let checker = Rc::new(CheckerTexture::new_solid(Color::new(0.2,0.3,0.1), Color::new(0.9,0.9,0.9)));
+    let ground_mat = Rc::new(Lambertian::new(checker));
+    objects.add(Rc::new(Sphere::new(Point3::new(0.0,-1000.0,0.0), 1000.0, ground_mat)));
+
+    let scalar = |value: Float| -> Rc<dyn Texture> {
+        Rc::new(SolidColor::new_rgb(value, value, value))
+    };
+    let color = |r: Float, g: Float, b: Float| -> Rc<dyn Texture> {
+        Rc::new(SolidColor::new_rgb(r, g, b))
+    };
+
+    let plastic = PrincipledBuilder::new()
+        .base_color(color(0.1, 0.2, 0.7))
+        .roughness(scalar(0.3))
+        .build();
+    let brushed = PrincipledBuilder::new()
+        .base_color(color(0.9, 0.6, 0.3))
+        .metallic(scalar(1.0))
+        .roughness(scalar(0.4))
+        .build();
+    let car_paint = PrincipledBuilder::new()
+        .base_color(color(0.6, 0.05, 0.05))
+        .metallic(scalar(0.5))
+        .roughness(scalar(0.5))
+        .clearcoat(scalar(1.0))
+        .build();
+    let velvet = PrincipledBuilder::new()
+        .base_color(color(0.5, 0.1, 0.4))
+        .roughness(scalar(1.0))
+        .specular(scalar(0.0))
+        .sheen(scalar(1.0))
+        .build();
+    let marble = PrincipledBuilder::new()
+        .base_color(Rc::new(NoiseTexture::new(4.0, rng)))
+        .roughness(scalar(0.1))
+        .transmission(scalar(0.5))
+        .build();
+
+    let materials: [Rc<dyn Material>; 5] = [
+        Rc::new(plastic), Rc::new(brushed), Rc::new(car_paint), Rc::new(velvet), Rc::new(marble)
+    ];
+    for (i, mat) in materials.into_iter().enumerate() {
+        let center = Point3::new(-4.4 + 2.2*i as Float, 1.0, 0.0);
+        objects.add(Rc::new(Sphere::new(center, 1.0, mat)));
+    }
+
+    objects
+}
+
+fn principled_cam() -> CameraBuilder {
+    let mut cam = conductors_cam();
+    cam.lookfrom(Point3::new(0.0, 2.0, 12.0));
+    cam
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 200;
+    const MAX_DEPTH: i32 = 16;
+ * */
+fn layered(rng: &mut RngGen) -> HittableList {
+    let mut objects = HittableList::new();
+    let checker = Rc::new(CheckerTexture::new_solid(Color::new(0.2,0.3,0.1), Color::new(0.9,0.9,0.9)));
+    let ground_mat = Rc::new(Lambertian::new(checker.clone()));
+    objects.add(Rc::new(Sphere::new(Point3::new(0.0,-1000.0,0.0), 1000.0, ground_mat)));
+
+    let rust = Rc::new(Lambertian::new_from_color(Color::new(0.4, 0.15, 0.05)));
+    let rust_mask = Rc::new(NoiseTexture::new(2.0, rng));
+    let rusty_metal = MixMaterial::new(Rc::new(Conductor::aluminium(0.2)), rust, rust_mask);
+
+    let red = Rc::new(Lambertian::new_from_color(Color::new(0.65, 0.05, 0.05)));
+    let varnished = Coated::new(red.clone(), 1.5, 0.0);
+    let satin = Coated::new(Rc::new(Lambertian::new(checker)), 1.5, 0.3);
+    let half_gold = MixMaterial::new_from_amount(red, Rc::new(Conductor::gold(0.1)), 0.5);
+
+    let materials: [Rc<dyn Material>; 4] = [
+        Rc::new(rusty_metal), Rc::new(varnished), Rc::new(satin), Rc::new(half_gold)
+    ];
+    for (i, mat) in materials.into_iter().enumerate() {
+        let center = Point3::new(-3.3 + 2.2*i as Float, 1.0, 0.0);
+        objects.add(Rc::new(Sphere::new(center, 1.0, mat)));
+    }
+
+    objects
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 100;
+    const MAX_DEPTH: i32 = 12;
+ * */
+fn bumps(rng: &mut RngGen) -> HittableList {
+    let mut objects = HittableList::new();
+    let ground_mat = Rc::new(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5)));
+    let ground = Rc::new(ZxRect::new(-20.0, 20.0, -20.0, 20.0, 0.0, ground_mat));
+    let ripples = Rc::new(NoiseTexture::new(1.0, rng));
+    objects.add(Rc::new(BumpMap::new(ground, ripples, 0.2)));
+
+    let noise = Rc::new(NoiseTexture::new(4.0, rng));
+    let plaster = Rc::new(Lambertian::new_from_color(Color::new(0.8, 0.8, 0.8)));
+    let sphere = Rc::new(Sphere::new(Point3::new(-1.2, 1.0, 0.0), 1.0, plaster));
+    objects.add(Rc::new(BumpMap::new(sphere, noise.clone(), 0.05)));
+
+    let hammered = Rc::new(Conductor::copper(0.2));
+    let sphere = Rc::new(Sphere::new(Point3::new(1.2, 1.0, 0.0), 1.0, hammered));
+    objects.add(Rc::new(BumpMap::new(sphere, noise, 0.02)));
+
+    objects
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 100;
+    const MAX_DEPTH: i32 = 50;
+ * */
+fn environment() -> HittableList {
+    let mut objects = HittableList::new();
+    let ground_mat = Rc::new(Lambertian::new_from_color(Color::new(0.5, 0.5, 0.5)));
+    objects.add(Rc::new(Sphere::new(Point3::new(0.0,-1000.0,0.0), 1000.0, ground_mat)));
+
+    let mats: [Rc<dyn Material>; 4] = [
+        Rc::new(Lambertian::new_from_color(Color::new(0.8, 0.8, 0.8))),
+        Rc::new(Conductor::gold(0.2)),
+        Rc::new(Dielectric::new(1.5)),
+        Rc::new(Conductor::silver(0.0)),
+    ];
+    for (i, mat) in mats.into_iter().enumerate() {
+        let center = Point3::new(-3.3 + 2.2*i as Float, 1.0, 0.0);
+        objects.add(Rc::new(Sphere::new(center, 1.0, mat)));
+    }
+
+    objects
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 50;
+    const MAX_DEPTH: i32 = 50;
+ * */
+fn delta_lights() -> HittableList {
+    let mut objects = HittableList::new();
+    let checker = Rc::new(CheckerTexture::new_solid(Color::new(0.2,0.3,0.1), Color::new(0.9,0.9,0.9)));
+    let ground_mat = Rc::new(Lambertian::new(checker));
+    objects.add(Rc::new(Sphere::new(Point3::new(0.0,-1000.0,0.0), 1000.0, ground_mat)));
+
+    let mats: [Rc<dyn Material>; 4] = [
+        Rc::new(Lambertian::new_from_color(Color::new(0.8, 0.3, 0.3))),
+        Rc::new(Conductor::gold(0.3)),
+        Rc::new(Lambertian::new_from_color(Color::new(0.3, 0.3, 0.8))),
+        Rc::new(Conductor::aluminium(0.5)),
+    ];
+    for (i, mat) in mats.into_iter().enumerate() {
+        let center = Point3::new(-3.3 + 2.2*i as Float, 1.0, 0.0);
+        objects.add(Rc::new(Sphere::new(center, 1.0, mat)));
+    }
+
+    objects
+}
+
+fn delta_lights_lights() -> Vec<Rc<dyn DeltaLight>> {
+    vec![
+        Rc::new(PointLight::new(Point3::new(-2.0, 4.0, 3.0), Color::new(12.0, 10.0, 8.0))),
+        Rc::new(SpotLight::new(Point3::new(3.0, 6.0, 2.0), &Point3::new(3.0, 0.0, 0.0),
+                               Color::new(20.0, 25.0, 40.0), 15.0, 25.0)),
+        Rc::new(DirectionalLight::new(&Vec3::new(1.0, -1.0, -0.5), Color::new(0.1, 0.1, 0.15))),
+    ]
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 50;
+    const MAX_DEPTH: i32 = 50;
+ * */
+fn luminaires() -> HittableList {
+    let mut objects = HittableList::new();
+    let white = Rc::new(Lambertian::new_from_color(Color::new(0.73, 0.73, 0.73)));
+    objects.add(Rc::new(ZxRect::new(-20.0, 20.0, -20.0, 20.0, 0.0, white.clone())));
+    objects.add(Rc::new(XyRect::new(-20.0, 20.0, 0.0, 20.0, -1.5, white)));
+
+    let red = Rc::new(Lambertian::new_from_color(Color::new(0.8, 0.3, 0.3)));
+    objects.add(Rc::new(Sphere::new(Point3::new(-1.5, 0.7, 0.5), 0.7, red)));
+    objects.add(Rc::new(Sphere::new(Point3::new(1.5, 0.7, 0.5), 0.7, Rc::new(Conductor::gold(0.3)))));
+
+    // Focused panel on the left, only its front face glows
+    let mut panel = DiffuseLight::new_from_color(&Color::new(0.002, 0.003, 0.004));
+    panel.two_sided(false).focus(8.0);
+    objects.add(Rc::new(YzRect::new(0.5, 2.5, -1.0, 1.0, -6.0, Rc::new(panel))));
+
+    objects
+}
+
+// Wall washers grazing the back wall
+fn luminaires_lights() -> Vec<Rc<dyn DeltaLight>> {
+    // NOTE(srp): Any type C .ies file works, it isn't in the repo
+    (0..3).map(|i| {
+        let x = -3.0 + 3.0*i as Float;
+        let mut profile = IesProfile::new(Path::new("luminaire.ies"));
+        profile.scale_to_lumens(800.0);
+        let light: Rc<dyn DeltaLight> = Rc::new(IesLight::new(
+                Point3::new(x, 4.0, -0.5), &Point3::new(x, 0.0, -1.5),
+                profile, Color::new(1.0, 0.85, 0.7)));
+        light
+    }).collect()
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 16.0 / 9.0;
+    const IMAGE_WIDTH:usize = 400;
+    const SAMPLES_PER_PIXEL:i32 = 200;
+    const MAX_DEPTH: i32 = 50;
+ * */
+// Small lights far behind an object in focus
+fn bokeh(rng: &mut RngGen) -> HittableList {
+    let mut objects = HittableList::new();
+    let ground_mat = Rc::new(Lambertian::new_from_color(Color::new(0.3, 0.3, 0.3)));
+    objects.add(Rc::new(Sphere::new(Point3::new(0.0,-1000.0,0.0), 1000.0, ground_mat)));
+    objects.add(Rc::new(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, Rc::new(Conductor::gold(0.2)))));
+
+    let mut lights = HittableList::new();
+    for _ in 0..40 {
+        let center = Point3::new(rng.range(-12.0, 12.0), rng.range(0.5, 6.0), rng.range(-30.0, -15.0));
+        let color = Color::new(rng.range(0.5, 1.0), rng.range(0.3, 0.8), rng.range(0.1, 0.5));
+        let light = Rc::new(DiffuseLight::new_from_color(&(20.0 * color)));
+        lights.add(Rc::new(Sphere::new(center, 0.1, light)));
+    }
+    objects.add(Rc::new(BVH::new(&mut lights, 0.0, 1.0, rng)));
+
+    objects
+}
+
+fn bokeh_cam() -> CameraBuilder {
+    let mut cam = CameraBuilder::new();
+    cam.lookfrom(Point3::new(0.0, 1.5, 6.0))
+        .lookat(Point3::new(0.0, 1.0, 0.0))
+        .vertical_fov(30.0)
+        .aperture(0.5)
+        .aperture_shape(Aperture::Polygon(6, 15.0))
+        .focus_dist(6.0)
+        .aspect_ratio(16.0 / 9.0);
+    cam
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 3.0 / 2.0;
+    const IMAGE_WIDTH:usize = 450;
+    const SAMPLES_PER_PIXEL:i32 = 100;
+    const MAX_DEPTH: i32 = 50;
+ * */
+// Daylight scene shot with the sunny 16 rule, 50mm f/16 1/100s ISO 100
+fn photographic_cam() -> CameraBuilder {
+    let mut cam = conductors_cam();
+    cam.physical(PhysicalCamera::new(50.0, 16.0, 1.0 / 100.0, 100.0))
+        .focus_dist(10.0);
+    cam
+}
+
+/* Suggested settings:
+ * IMAGE
+    const ASPECT_RATIO:Float = 3.0 / 2.0;
+    const IMAGE_WIDTH:usize = 450;
+    const SAMPLES_PER_PIXEL:i32 = 200;
+    const MAX_DEPTH: i32 = 50;
+ * */
+// Wide open 50mm double Gauss lens focused on the front of the spheres
+fn double_gauss_cam() -> CameraBuilder {
+    let mut cam = conductors_cam();
+    cam.lens_system(LensSystem::double_gauss_50mm())
+        .focus_dist(9.0);
+    cam
+}
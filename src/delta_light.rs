@@ -0,0 +1,108 @@
+use super::float::Float;
+use super::vec3::Vec3;
+type Point3 = Vec3;
+use super::color::Color;
+
+// Lights with no area, rays never hit them so they are only reached with
+// shadow rays.
+pub trait DeltaLight {
+    // Unit direction from `p` towards the light, distance to it and the
+    // radiance arriving at `p` if nothing is in the way
+    fn sample(&self, p: &Point3) -> Option<(Vec3, Float, Color)>;
+}
+
+pub struct PointLight {
+    position: Point3,
+    intensity: Color,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        Self { position, intensity }
+    }
+}
+
+impl DeltaLight for PointLight {
+    fn sample(&self, p: &Point3) -> Option<(Vec3, Float, Color)> {
+        let to_light = &self.position - p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared <= 0.0 {
+            return None
+        }
+        let distance = distance_squared.sqrt();
+        Some((to_light / distance, distance, &self.intensity / distance_squared))
+    }
+}
+
+// Point light shining into a cone, fading out between the inner and outer
+// angles
+pub struct SpotLight {
+    position: Point3,
+    direction: Vec3,
+    intensity: Color,
+    cos_inner: Float,
+    cos_outer: Float,
+}
+
+impl SpotLight {
+    // Angles in degrees from the center of the cone
+    pub fn new(position: Point3, look_at: &Point3, intensity: Color,
+               inner_angle: Float, outer_angle: Float) -> Self {
+        let direction = (look_at - &position).unit_vector();
+        let outer_angle = outer_angle.max(inner_angle);
+        Self {
+            position,
+            direction,
+            intensity,
+            cos_inner: inner_angle.to_radians().cos(),
+            cos_outer: outer_angle.to_radians().cos(),
+        }
+    }
+
+    fn falloff(&self, cos_theta: Float) -> Float {
+        if cos_theta >= self.cos_inner {
+            return 1.0
+        }
+        if cos_theta <= self.cos_outer {
+            return 0.0
+        }
+        let t = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t*t*(3.0 - 2.0*t)
+    }
+}
+
+impl DeltaLight for SpotLight {
+    fn sample(&self, p: &Point3) -> Option<(Vec3, Float, Color)> {
+        let to_light = &self.position - p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared <= 0.0 {
+            return None
+        }
+        let distance = distance_squared.sqrt();
+        let direction = to_light / distance;
+        let falloff = self.falloff(-Vec3::dot(&direction, &self.direction));
+        if falloff <= 0.0 {
+            return None
+        }
+        Some((direction, distance, (falloff / distance_squared) * &self.intensity))
+    }
+}
+
+// Parallel rays coming from infinitely far away, like sunlight
+pub struct DirectionalLight {
+    to_light: Vec3,
+    irradiance: Color,
+}
+
+impl DirectionalLight {
+    // `direction` is the way the light travels
+    pub fn new(direction: &Vec3, irradiance: Color) -> Self {
+        Self { to_light: -direction.unit_vector(), irradiance }
+    }
+}
+
+impl DeltaLight for DirectionalLight {
+    fn sample(&self, _p: &Point3) -> Option<(Vec3, Float, Color)> {
+        Some((self.to_light.copy(), Float::INFINITY, self.irradiance.copy()))
+    }
+}
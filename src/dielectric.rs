@@ -5,14 +5,40 @@ use super::ray::Ray;
 use super::color::Color;
 use super::hittable::HitRecord;
 use super::rng_float::RngGen;
+use super::onb::ONB;
+use super::microfacet::GGX;
+use super::fresnel::fresnel_dielectric;
 
 pub struct Dielectric {
-    ior: Float // index of refraction
+    ior: Float, // index of refraction
+    distribution: GGX,
+    absorption: Color, // Beer-Lambert coefficient per unit of distance
 }
 
 impl Dielectric {
     pub fn new(ior: Float) -> Self {
-        Self { ior }
+        Self::new_rough(ior, 0.0)
+    }
+
+    // Frosted glass, roughness zero is a perfectly smooth interface
+    pub fn new_rough(ior: Float, roughness: Float) -> Self {
+        Self { ior, distribution: GGX::new(roughness), absorption: Color::zero() }
+    }
+
+    // Colored glass, `transmittance` is the fraction of light left after
+    // traveling `distance` inside the object.
+    pub fn new_absorbing(ior: Float, roughness: Float,
+                         transmittance: Color, distance: Float) -> Self {
+        if distance.is_nan() || distance <= 0.0 {
+            eprintln!("Absorbing glass needs a positive distance, got {distance}, using clear glass");
+            return Self::new_rough(ior, roughness)
+        }
+        let coefficient = |t: Float| -t.clamp(1e-6, 1.0).ln() / distance;
+        let absorption = Color::new(
+            coefficient(transmittance.x()),
+            coefficient(transmittance.y()),
+            coefficient(transmittance.z()));
+        Self { ior, distribution: GGX::new(roughness), absorption }
     }
 
     fn reflectance(cosine: Float, refraction_ratio: Float) -> Float {
@@ -21,31 +47,133 @@ impl Dielectric {
         let r0 = r0 * r0;
         r0 + (1.0 - r0)*(1.0 - cosine).powi(5)
     }
+
+    // NOTE(srp): When leaving the object the incoming ray started at the
+    // previous hit on the same object, so its length up to this hit is the
+    // distance traveled inside it.
+    fn attenuation(&self, r_in: &Ray, hit: &HitRecord) -> Color {
+        if hit.in_front_face() {
+            return Color::one()
+        }
+        let distance = hit.t() * r_in.direction().length();
+        Color::new(
+            (-self.absorption.x() * distance).exp(),
+            (-self.absorption.y() * distance).exp(),
+            (-self.absorption.z() * distance).exp())
+    }
+
+    fn smooth_direction(unit_direction: &Vec3, normal: &Vec3,
+                        refraction_ratio: Float, rng: &mut RngGen) -> Vec3 {
+        let cos_theta = Vec3::dot(&-unit_direction, normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta*cos_theta).sqrt();
+
+        let cannot_refract: bool = refraction_ratio * sin_theta > 1.0;
+        let reflect: bool = Self::reflectance(cos_theta, refraction_ratio) > rng.get();
+
+        if cannot_refract || reflect {
+            unit_direction.reflect(normal)
+        } else {
+            unit_direction.refract(normal, refraction_ratio)
+        }
+    }
+
+    // Walter et al. 2007, "Microfacet Models for Refraction through Rough
+    // Surfaces", sampling only the microfacets visible from the ray.
+    // Returns the new direction and its weight G2/G1.
+    fn rough_direction(&self, unit_direction: &Vec3, normal: &Vec3,
+                       refraction_ratio: Float, rng: &mut RngGen) -> Option<(Vec3, Float)> {
+        let frame = ONB::build_from_w(normal);
+        let wo = frame.to_local(&-unit_direction);
+        if wo.z() <= 0.0 {
+            return None
+        }
+
+        let m = self.distribution.sample_visible_normal(&wo, rng.get(), rng.get());
+        let cos_theta = Vec3::dot(&wo, &m);
+        let wi = if fresnel_dielectric(cos_theta, refraction_ratio) > rng.get() {
+            let wi = (-&wo).reflect(&m);
+            if wi.z() <= 0.0 { return None }
+            wi
+        } else {
+            let wi = (-&wo).refract(&m, refraction_ratio);
+            if wi.z() >= 0.0 { return None }
+            wi
+        };
+
+        let weight = self.distribution.g2(&wo, &wi) / self.distribution.g1(&wo);
+        Some((frame.local_vec(&wi), weight))
+    }
+
+    // BSDF times cosine and density of rough_direction() for `scattered`
+    fn rough_eval_pdf(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3)
+            -> Option<(Float, Float)> {
+        if self.distribution.is_smooth() {
+            return None
+        }
+        let refraction_ratio = if hit.in_front_face() {
+            self.ior.recip()
+        } else {
+            self.ior
+        };
+        let frame = ONB::build_from_w(hit.normal());
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let wi = frame.to_local(scattered);
+
+        if wi.z() > 0.0 {
+            let (m, f_cos, pdf) = self.distribution.reflection(&wo, &wi)?;
+            let fresnel = fresnel_dielectric(Vec3::dot(&wo, &m), refraction_ratio);
+            Some((fresnel * f_cos, fresnel * pdf))
+        } else {
+            let (m, f_cos, pdf) = self.distribution.transmission(&wo, &wi, refraction_ratio)?;
+            let fresnel = fresnel_dielectric(Vec3::dot(&wo, &m), refraction_ratio);
+            Some(((1.0 - fresnel) * f_cos, (1.0 - fresnel) * pdf))
+        }
+    }
 }
 
 impl Material for Dielectric {
     fn scatter(&self, r_in: &Ray, hit: &HitRecord, rng: &mut RngGen) -> Scatter {
-        let new_color = Color::one();
         let refraction_ratio = if hit.in_front_face() {
             self.ior.recip()
         } else {
             self.ior
         };
 
-        let ref unit_direction: Vec3 = r_in.direction().unit_vector();
-        let cos_theta = Vec3::dot(&-unit_direction, hit.normal()).min(1.0);
-        let sin_theta = (1.0 - cos_theta*cos_theta).sqrt();
-
-        let cannot_refract: bool = refraction_ratio * sin_theta > 1.0;
-        let reflect: bool = Self::reflectance(cos_theta, refraction_ratio) > rng.get();
-
-        let direction: Vec3 = if cannot_refract || reflect {
-            unit_direction.reflect(hit.normal())
+        let unit_direction: Vec3 = r_in.direction().unit_vector();
+        let (direction, weight) = if self.distribution.is_smooth() {
+            (Self::smooth_direction(&unit_direction, hit.normal(), refraction_ratio, rng), 1.0)
         } else {
-            unit_direction.refract(hit.normal(), refraction_ratio)
+            match self.rough_direction(&unit_direction, hit.normal(), refraction_ratio, rng) {
+                Some(sample) => sample,
+                None => return Scatter::None
+            }
         };
 
+        let new_color = weight * self.attenuation(r_in, hit);
         let new_ray = Ray::new(hit.p(), &direction, r_in.time());
-        Scatter::Some(new_ray, new_color)
+        if self.distribution.is_smooth() {
+            Scatter::Specular(new_ray, new_color)
+        } else {
+            let pdf = self.pdf(r_in, hit, &direction.unit_vector());
+            Scatter::Some(new_ray, new_color, pdf)
+        }
+    }
+
+    fn eval(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Color {
+        match self.rough_eval_pdf(r_in, hit, scattered) {
+            Some((f_cos, _)) => f_cos * self.attenuation(r_in, hit),
+            None => Color::zero()
+        }
+    }
+
+    fn pdf(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Float {
+        match self.rough_eval_pdf(r_in, hit, scattered) {
+            Some((_, pdf)) => pdf,
+            None => 0.0
+        }
+    }
+
+    fn has_non_specular(&self) -> bool {
+        !self.distribution.is_smooth()
     }
 }
@@ -1,26 +1,85 @@
 use super::float::Float;
 use super::vec3::Vec3;
-type Point3 = Vec3;
-use super::color::Color;
+use super::color::{Color, PHOTOMETRIC_SCALE};
 use super::ray::Ray;
 use super::hittable::HitRecord;
 use super::rng_float::RngGen;
 use super::texture::{Texture, SolidColor};
 use super::material::{Material, Scatter};
+use super::ies::IesProfile;
+use super::onb::ONB;
 
 use std::rc::Rc;
 
 pub struct DiffuseLight {
-    emit: Rc<dyn Texture>
+    emit: Rc<dyn Texture>,
+    two_sided: bool,
+    focus: Float,
+    profile: Option<IesProfile>,
+    area: Float,
 }
 
 impl DiffuseLight {
     pub fn new(emit: Rc<dyn Texture>) -> Self {
-        Self { emit }
+        Self { emit, two_sided: true, focus: 0.0, profile: None, area: 1.0 }
     }
 
     pub fn new_from_color(c: &Color) -> Self {
-        Self { emit: Rc::new(SolidColor::new(c.copy())) }
+        Self::new(Rc::new(SolidColor::new(c.copy())))
+    }
+
+    // Single sided lights only emit towards their outward normal
+    pub fn two_sided(&mut self, two_sided: bool) -> &mut Self {
+        self.two_sided = two_sided;
+        self
+    }
+
+    // Emission falls off with cos^focus away from the normal, 0 emits evenly
+    // in every direction
+    pub fn focus(&mut self, focus: Float) -> &mut Self {
+        self.focus = focus.max(0.0);
+        self
+    }
+
+    // The profile's candela are spread over the emitter's `area`, in square
+    // scene units (meters, like IesLight), and the texture tints them.
+    // Vertical angle 0 points along the normal.
+    pub fn ies_profile(&mut self, profile: IesProfile, area: Float) -> &mut Self {
+        self.profile = Some(profile);
+        self.area = area;
+        self
+    }
+
+    // How much of the emission leaves towards the unit direction `to_viewer`
+    fn directional_factor(&self, hit: &HitRecord, to_viewer: &Vec3) -> Float {
+        let cos_theta = Vec3::dot(hit.normal(), to_viewer).clamp(0.0, 1.0);
+        let mut factor = 1.0;
+        if self.focus > 0.0 {
+            // NOTE(srp): Normalized so focusing a light doesn't change the
+            // total power it gives off, only where it goes.
+            factor *= 0.5 * (self.focus + 2.0) * cos_theta.powf(self.focus);
+        }
+        if let Some(profile) = self.profile.as_ref() {
+            // Horizontal angle 0 follows the texture's u direction if the
+            // shape has one
+            let (u, v) = if hit.dpdu().length_squared() > 0.0 {
+                let v = Vec3::cross(hit.normal(), hit.dpdu()).unit_vector();
+                (Vec3::cross(&v, hit.normal()), v)
+            } else {
+                let frame = ONB::build_from_w(hit.normal());
+                (frame.u().copy(), frame.v().copy())
+            };
+            let vertical = cos_theta.acos().to_degrees();
+            let horizontal = Vec3::dot(to_viewer, &v).atan2(Vec3::dot(to_viewer, &u)).to_degrees();
+            // NOTE(srp): Intensity over the area the emitter shows the viewer
+            // is radiance, converted the same way IesLight converts it.
+            let projected_area = self.area * cos_theta.max(1e-4);
+            if projected_area <= 0.0 {
+                return 0.0
+            }
+            factor *= PHOTOMETRIC_SCALE * profile.intensity(vertical, horizontal) / projected_area;
+        }
+        factor
     }
 }
 
@@ -29,7 +88,19 @@ impl Material for DiffuseLight {
         Scatter::None
     }
 
-    fn emmited(&self, u: Float, v: Float, p: &Point3) -> Color {
-        self.emit.value(u, v, p)
+    fn emmited(&self, r_in: &Ray, hit: &HitRecord) -> Color {
+        if !self.two_sided && !hit.in_front_face() {
+            return Color::zero()
+        }
+        let emit = self.emit.value(hit.u(), hit.v(), hit.p());
+        if self.focus <= 0.0 && self.profile.is_none() {
+            return emit
+        }
+        let to_viewer = -r_in.direction().unit_vector();
+        self.directional_factor(hit, &to_viewer) * emit
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
     }
 }
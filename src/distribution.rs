@@ -0,0 +1,57 @@
+use super::float::Float;
+
+// Piecewise constant distribution over [0, 1]
+pub struct Distribution {
+    func: Vec<Float>,
+    cdf: Vec<Float>,
+    integral: Float,
+}
+
+impl Distribution {
+    pub fn new(func: Vec<Float>) -> Self {
+        let n = func.len();
+        let mut cdf = Vec::with_capacity(n + 1);
+        // NOTE(srp): Large maps lose too much precision summing in f32
+        let mut sum: f64 = 0.0;
+        cdf.push(0.0);
+        for f in func.iter() {
+            sum += f.max(0.0) as f64 / n as f64;
+            cdf.push(sum as Float);
+        }
+        let integral = sum as Float;
+
+        if integral <= 0.0 {
+            // Black map, sample it uniformly
+            let cdf = (0..=n).map(|i| i as Float / n as Float).collect();
+            return Self { func: vec![1.0; n], cdf, integral: 0.0 }
+        }
+        for c in cdf.iter_mut() {
+            *c /= integral;
+        }
+        Self { func, cdf, integral }
+    }
+
+    // Returns a point in [0, 1) and the index of the piece it falls in
+    pub fn sample(&self, u: Float) -> (Float, usize) {
+        let n = self.func.len();
+        let index = self.cdf.partition_point(|&c| c <= u).clamp(1, n) - 1;
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 {
+            ((u - self.cdf[index]) / width).clamp(0.0, 1.0)
+        } else {
+            0.5
+        };
+        (((index as Float + offset) / n as Float).min(1.0 - Float::EPSILON), index)
+    }
+
+    pub fn integral(&self) -> Float {
+        self.integral
+    }
+
+    pub fn pdf(&self, index: usize) -> Float {
+        if self.integral <= 0.0 {
+            return 1.0
+        }
+        self.func[index].max(0.0) / self.integral
+    }
+}
@@ -0,0 +1,136 @@
+use super::float::{Float, PI};
+use super::vec3::Vec3;
+use super::color::Color;
+use super::rng_float::RngGen;
+use super::distribution::Distribution;
+
+use stb_image::image::{LoadResult, load_with_depth};
+use std::path::Path;
+
+// Equirectangular image surrounding the scene, +y is up and the center of the
+// image looks towards +x before rotating. Radiance .hdr files keep their
+// linear values, other formats are taken as sRGB and linearized to [0, 1].
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    sin_theta: Float,
+    cos_theta: Float,
+    intensity: Float,
+    rows: Distribution,
+    columns: Vec<Distribution>,
+}
+
+impl EnvironmentMap {
+    // `rotation` is in degrees around +y, like RotateY
+    pub fn new(path: &Path, rotation: Float, intensity: Float) -> Self {
+        let (width, height, pixels) = match load_with_depth(path, 3, false) {
+            LoadResult::ImageF32(img) => {
+                let pixels = img.data.chunks(img.depth)
+                    .map(|p| Color::new(p[0], p[1], p[2]))
+                    .collect();
+                (img.width, img.height, pixels)
+            },
+            LoadResult::ImageU8(img) => {
+                let pixels = img.data.chunks(img.depth)
+                    .map(|p| Color::new(srgb_to_linear(p[0]), srgb_to_linear(p[1]), srgb_to_linear(p[2])))
+                    .collect();
+                (img.width, img.height, pixels)
+            },
+            LoadResult::Error(_) => (1, 1, vec![Color::new(0.0, 1.0, 1.0)]) // Missing image cyan
+        };
+
+        // Pixels near the poles cover less solid angle
+        let columns: Vec<Distribution> = (0..height).map(|j| {
+            let sin_theta = (PI * (j as Float + 0.5) / height as Float).sin();
+            let row = &pixels[j*width..(j+1)*width];
+            Distribution::new(row.iter().map(|c| luminance(c) * sin_theta).collect())
+        }).collect();
+        let rows = Distribution::new(columns.iter().map(|c| c.integral()).collect());
+
+        let radians = rotation.to_radians();
+        Self {
+            width,
+            height,
+            pixels,
+            sin_theta: radians.sin(),
+            cos_theta: radians.cos(),
+            intensity,
+            rows,
+            columns,
+        }
+    }
+
+    pub fn value(&self, direction: &Vec3) -> Color {
+        let (u, v) = self.get_uv(direction);
+        let (i, j) = self.pixel_index(u, v);
+        self.intensity * &self.pixels[i + j*self.width]
+    }
+
+    // Solid angle density of `random` picking `direction`
+    pub fn pdf_value(&self, direction: &Vec3) -> Float {
+        let (u, v) = self.get_uv(direction);
+        let sin_theta = (PI * v).sin();
+        if sin_theta <= 0.0 {
+            return 0.0
+        }
+        let (i, j) = self.pixel_index(u, v);
+        let uv_pdf = self.rows.pdf(j) * self.columns[j].pdf(i);
+        uv_pdf / (2.0 * PI * PI * sin_theta)
+    }
+
+    // Direction picked proportionally to the brightness of the map
+    pub fn random(&self, rng: &mut RngGen) -> Vec3 {
+        let (v, j) = self.rows.sample(rng.get());
+        let (u, _) = self.columns[j].sample(rng.get());
+        let theta = PI * v;
+        let phi = 2.0 * PI * u;
+        let map_direction = Vec3::new(
+            -phi.cos() * theta.sin(),
+            theta.cos(),
+            -phi.sin() * theta.sin());
+        self.to_world(&map_direction)
+    }
+
+    // NOTE(srp): `v` grows downwards here, it goes from +y to -y. The map is
+    // seen from inside, so +z is to the right of the center.
+    fn get_uv(&self, direction: &Vec3) -> (Float, Float) {
+        let d = self.to_map(&direction.unit_vector());
+        let theta = d.y().clamp(-1.0, 1.0).acos();
+        let phi = d.z().atan2(d.x()) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    fn pixel_index(&self, u: Float, v: Float) -> (usize, usize) {
+        let i = ((u * self.width as Float) as usize).min(self.width - 1);
+        let j = ((v * self.height as Float) as usize).min(self.height - 1);
+        (i, j)
+    }
+
+    fn to_map(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta*v.x() - self.sin_theta*v.z(),
+            v.y(),
+            self.sin_theta*v.x() + self.cos_theta*v.z())
+    }
+
+    fn to_world(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta*v.x() + self.sin_theta*v.z(),
+            v.y(),
+            -self.sin_theta*v.x() + self.cos_theta*v.z())
+    }
+}
+
+fn luminance(c: &Color) -> Float {
+    0.2126*c.x() + 0.7152*c.y() + 0.0722*c.z()
+}
+
+fn srgb_to_linear(value: u8) -> Float {
+    let c = value as Float / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -2,3 +2,4 @@ pub type Float = f32; // Can be changed to f32 or f64 if better
 
 pub const PI: Float = std::f32::consts::PI;
 pub const FRAC_1_PI: Float = std::f32::consts::FRAC_1_PI;
+pub const SQRT_2: Float = std::f32::consts::SQRT_2;
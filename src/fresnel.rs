@@ -0,0 +1,54 @@
+use super::float::Float;
+use super::color::Color;
+
+pub fn schlick(cos_theta: Float, f0: Float) -> Float {
+    f0 + (1.0 - f0)*(1.0 - cos_theta.clamp(0.0, 1.0)).powi(5)
+}
+
+pub fn schlick_color(cos_theta: Float, f0: &Color) -> Color {
+    let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    f0 + weight*(Color::one() - f0)
+}
+
+// Exact Fresnel reflectance for a conductor with complex index of refraction
+// eta + i*k, evaluated per color channel.
+pub fn fresnel_conductor(cos_theta_i: Float, eta: &Color, k: &Color) -> Color {
+    Color::new(
+        fresnel_conductor_channel(cos_theta_i, eta.x(), k.x()),
+        fresnel_conductor_channel(cos_theta_i, eta.y(), k.y()),
+        fresnel_conductor_channel(cos_theta_i, eta.z(), k.z()))
+}
+
+fn fresnel_conductor_channel(cos_theta_i: Float, eta: Float, k: Float) -> Float {
+    let cos2 = cos_theta_i.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let eta2 = eta*eta;
+    let k2 = k*k;
+
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = (t0*t0 + 4.0*eta2*k2).sqrt();
+    let t1 = a2_plus_b2 + cos2;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos_theta_i.clamp(0.0, 1.0) * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2*a2_plus_b2 + sin2*sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    0.5 * (rp + rs)
+}
+
+// Exact Fresnel reflectance for a dielectric interface.
+// NOTE(srp): refraction_ratio := $\frac{\eta_i}{\eta_t}$
+pub fn fresnel_dielectric(cos_theta_i: Float, refraction_ratio: Float) -> Float {
+    let cos_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_t = refraction_ratio*refraction_ratio * (1.0 - cos_i*cos_i);
+    if sin2_t >= 1.0 {
+        return 1.0 // Total internal reflection
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let rs = (refraction_ratio*cos_i - cos_t) / (refraction_ratio*cos_i + cos_t);
+    let rp = (cos_i - refraction_ratio*cos_t) / (cos_i + refraction_ratio*cos_t);
+    0.5 * (rs*rs + rp*rp)
+}
@@ -6,6 +6,7 @@ type Point3 = Vec3;
 use super::ray::Ray;
 use super::material::{Material, NullMaterial};
 use super::aabb::AABB;
+use super::hittable_list::HittableList;
 
 pub struct HitRecord {
     p: Point3,
@@ -15,6 +16,8 @@ pub struct HitRecord {
     u: Float,
     v: Float,
     in_front_face: bool,
+    dpdu: Vec3,
+    dpdv: Vec3,
 }
 
 impl HitRecord {
@@ -28,7 +31,7 @@ impl HitRecord {
         let mut normal: Vec3 = Vec3::zero();
         let mut in_front_face: bool = false;
         Self::front_face_and_normal(&r, outward_normal, &mut normal, &mut in_front_face);
-        Self { p: r.at(t), normal, mat, t, u, v, in_front_face }
+        Self { p: r.at(t), normal, mat, t, u, v, in_front_face, dpdu: Vec3::zero(), dpdv: Vec3::zero() }
     }
 
     pub fn from_settings(p: Point3,
@@ -38,7 +41,7 @@ impl HitRecord {
                          u: Float,
                          v: Float,
                          in_front_face: bool) -> Self {
-        Self { p, normal, mat, t, u, v, in_front_face }
+        Self { p, normal, mat, t, u, v, in_front_face, dpdu: Vec3::zero(), dpdv: Vec3::zero() }
     }
 
     pub fn null() -> Self {
@@ -50,6 +53,8 @@ impl HitRecord {
             u: -1.0,
             v: -1.0,
             in_front_face: false,
+            dpdu: Vec3::zero(),
+            dpdv: Vec3::zero(),
         }
     }
 
@@ -89,6 +94,29 @@ impl HitRecord {
         self.v
     }
 
+    // Surface tangents, the derivatives of p along u and v
+    pub fn dpdu(&self) -> &Vec3 {
+        &(self.dpdu)
+    }
+
+    pub fn dpdv(&self) -> &Vec3 {
+        &(self.dpdv)
+    }
+
+    pub fn set_tangents(&mut self, dpdu: Vec3, dpdv: Vec3) {
+        self.dpdu = dpdu;
+        self.dpdv = dpdv;
+    }
+
+    // Replaces the normal used for shading, keeping the face that was hit
+    pub fn set_shading_normal(&mut self, outward_normal: Vec3) {
+        self.normal = if self.in_front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        }
+    }
+
     pub fn set_face_normal(&mut self, r: &Ray, outward_normal: Vec3) {
         Self::front_face_and_normal(r, outward_normal, &mut self.normal, &mut self.in_front_face);
     }
@@ -112,4 +140,23 @@ impl HitRecord {
 pub trait Hittable {
     fn hit(&self, r:&Ray, t_min:Float, t_max:Float, rng: &mut RngGen) -> Option<HitRecord>;
     fn bounding_box(&self, time0: Float, time1: Float) -> Option<AABB>;
+
+    // Whether the renderer should sample this as a light, it has to
+    // implement pdf_value and random too
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    // Adds the emissive objects contained in this one to `lights`
+    fn collect_lights(&self, _lights: &mut HittableList) {}
+
+    // Solid angle density of `random` picking `direction` from `origin`
+    fn pdf_value(&self, _origin: &Point3, _direction: &Vec3, _rng: &mut RngGen) -> Float {
+        0.0
+    }
+
+    // Direction from `origin` towards a random point on this object
+    fn random(&self, _origin: &Point3, _rng: &mut RngGen) -> Vec3 {
+        Vec3::right()
+    }
 }
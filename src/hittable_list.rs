@@ -4,6 +4,8 @@ use super::rng_float::RngGen;
 use super::ray::*;
 use super::hittable::*;
 use super::aabb::AABB;
+use super::vec3::Vec3;
+type Point3 = Vec3;
 
 // NOTE(srp): Podríamos querer cambiar Rc por std::sync::Arc
 pub struct HittableList {
@@ -30,6 +32,15 @@ impl HittableList {
     pub fn objects_mut(&mut self) -> &mut Vec<Rc<dyn Hittable>> {
         &mut self.objects
     }
+
+    // Adds `object` if it's a light, otherwise the lights inside of it
+    pub fn add_lights_from(&mut self, object: &Rc<dyn Hittable>) {
+        if object.is_emissive() {
+            self.add(object.clone());
+        } else {
+            object.collect_lights(self);
+        }
+    }
 }
 
 impl Hittable for HittableList {
@@ -72,4 +83,29 @@ impl Hittable for HittableList {
 
         output
     }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        for object in &self.objects {
+            lights.add_lights_from(object);
+        }
+    }
+
+    // Every object is picked with the same probability
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        if self.objects.is_empty() {
+            return 0.0
+        }
+        let sum: Float = self.objects.iter()
+            .map(|object| object.pdf_value(origin, direction, rng))
+            .sum();
+        sum / self.objects.len() as Float
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut RngGen) -> Vec3 {
+        if self.objects.is_empty() {
+            return Vec3::right()
+        }
+        let index = ((rng.get() * self.objects.len() as Float) as usize).min(self.objects.len() - 1);
+        self.objects[index].random(origin, rng)
+    }
 }
@@ -0,0 +1,313 @@
+use super::float::{Float, PI};
+use super::vec3::Vec3;
+type Point3 = Vec3;
+use super::color::{Color, PHOTOMETRIC_SCALE};
+use super::onb::ONB;
+use super::delta_light::DeltaLight;
+
+use std::path::Path;
+
+// Luminous intensity distribution from an IES LM-63 file, only type C
+// photometry is supported (vertical angle 0 points down the luminaire's axis).
+pub struct IesProfile {
+    vertical_angles: Vec<Float>,   // Degrees, increasing
+    horizontal_angles: Vec<Float>, // Degrees, increasing
+    candela: Vec<Vec<Float>>,      // One row of vertical samples per horizontal angle
+}
+
+impl IesProfile {
+    pub fn new(path: &Path) -> Self {
+        let parsed = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Self::parse(&text));
+        match parsed {
+            Ok(profile) => profile,
+            Err(e) => {
+                // Missing profile shines the same way everywhere
+                eprintln!("Couldn't load IES profile {}: {}", path.display(), e);
+                Self::new_uniform(100.0)
+            }
+        }
+    }
+
+    pub fn new_uniform(candela: Float) -> Self {
+        Self {
+            vertical_angles: vec![0.0, 180.0],
+            horizontal_angles: vec![0.0],
+            candela: vec![vec![candela, candela]],
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let tilt = loop {
+            match lines.next() {
+                Some(line) if line.trim_start().starts_with("TILT=") => break line.trim().to_string(),
+                Some(_) => continue, // Keywords
+                None => return Err("missing TILT line".to_string())
+            }
+        };
+
+        let rest: Vec<&str> = lines.collect();
+        let mut numbers = rest.iter()
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<Float>().map_err(|_| format!("bad number '{token}'")));
+        let mut next = move || numbers.next().unwrap_or(Err("file ends too early".to_string()));
+
+        if tilt == "TILT=INCLUDE" {
+            let _lamp_to_luminaire_geometry = next()?;
+            let pairs = next()? as usize;
+            for _ in 0..2*pairs {
+                next()?;
+            }
+        } else if tilt != "TILT=NONE" {
+            return Err("TILT files aren't supported".to_string())
+        }
+
+        let _lamps = next()?;
+        let _lumens_per_lamp = next()?;
+        let multiplier = next()?;
+        let vertical_count = next()? as usize;
+        let horizontal_count = next()? as usize;
+        let photometric_type = next()? as i32;
+        let _units = next()?;
+        let _width = next()?;
+        let _length = next()?;
+        let _height = next()?;
+        let ballast_factor = next()?;
+        let ballast_lamp_factor = next()?;
+        let _input_watts = next()?;
+
+        if photometric_type != 1 {
+            return Err("only type C photometry is supported".to_string())
+        }
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err("no angles".to_string())
+        }
+
+        let vertical_angles = (0..vertical_count).map(|_| next()).collect::<Result<Vec<_>, _>>()?;
+        let horizontal_angles = (0..horizontal_count).map(|_| next()).collect::<Result<Vec<_>, _>>()?;
+        let scale = multiplier * ballast_factor * ballast_lamp_factor;
+        let mut candela = Vec::with_capacity(horizontal_count);
+        for _ in 0..horizontal_count {
+            let row = (0..vertical_count).map(|_| next().map(|c| scale * c))
+                .collect::<Result<Vec<_>, _>>()?;
+            candela.push(row);
+        }
+
+        Ok(Self { vertical_angles, horizontal_angles, candela })
+    }
+
+    // Candela towards the angles in degrees, the horizontal angle goes
+    // around the luminaire's axis
+    pub fn intensity(&self, vertical: Float, horizontal: Float) -> Float {
+        let horizontal = self.fold_horizontal(horizontal.rem_euclid(360.0));
+        let (h0, h1, th) = lerp_indices(&self.horizontal_angles, horizontal);
+        let (v0, v1, tv) = match vertical_in_range(&self.vertical_angles, vertical) {
+            true => lerp_indices(&self.vertical_angles, vertical),
+            false => return 0.0
+        };
+        let at = |h: usize| (1.0 - tv)*self.candela[h][v0] + tv*self.candela[h][v1];
+        (1.0 - th)*at(h0) + th*at(h1)
+    }
+
+    // Total luminous flux, integrated numerically
+    pub fn lumens(&self) -> Float {
+        const STEPS: usize = 180;
+        let mut flux = 0.0;
+        for i in 0..STEPS {
+            let vertical = 180.0 * (i as Float + 0.5) / STEPS as Float;
+            let solid_angle = vertical.to_radians().sin() * (PI / STEPS as Float) * (2.0*PI / (2*STEPS) as Float);
+            for j in 0..2*STEPS {
+                let horizontal = 360.0 * (j as Float + 0.5) / (2*STEPS) as Float;
+                flux += self.intensity(vertical, horizontal) * solid_angle;
+            }
+        }
+        flux
+    }
+
+    // Rescales the profile so it emits `lumens` in total
+    pub fn scale_to_lumens(&mut self, lumens: Float) -> &mut Self {
+        let current = self.lumens();
+        if current > 0.0 {
+            let scale = lumens / current;
+            for row in self.candela.iter_mut() {
+                for c in row.iter_mut() {
+                    *c *= scale;
+                }
+            }
+        }
+        self
+    }
+
+    // Uses the symmetry implied by the last horizontal angle
+    fn fold_horizontal(&self, angle: Float) -> Float {
+        let first = self.horizontal_angles[0];
+        let last = self.horizontal_angles[self.horizontal_angles.len() - 1];
+        if self.horizontal_angles.len() == 1 {
+            first
+        } else if last <= 90.0 {
+            let angle = if angle > 180.0 { 360.0 - angle } else { angle };
+            if angle > 90.0 { 180.0 - angle } else { angle }
+        } else if first >= 90.0 && last <= 270.0 {
+            // Symmetric about the 90-270 plane
+            if angle < 90.0 {
+                180.0 - angle
+            } else if angle > 270.0 {
+                540.0 - angle
+            } else {
+                angle
+            }
+        } else if last <= 180.0 {
+            if angle > 180.0 { 360.0 - angle } else { angle }
+        } else {
+            angle
+        }
+    }
+}
+
+fn vertical_in_range(angles: &[Float], angle: Float) -> bool {
+    angle >= angles[0] && angle <= angles[angles.len() - 1]
+}
+
+// Indices around `x` in `angles` and how far `x` is between them
+fn lerp_indices(angles: &[Float], x: Float) -> (usize, usize, Float) {
+    let n = angles.len();
+    if n == 1 || x <= angles[0] {
+        return (0, 0, 0.0)
+    }
+    if x >= angles[n - 1] {
+        return (n - 1, n - 1, 0.0)
+    }
+    let i1 = angles.partition_point(|&a| a <= x).min(n - 1);
+    let i0 = i1 - 1;
+    let span = angles[i1] - angles[i0];
+    let t = if span > 0.0 { (x - angles[i0]) / span } else { 0.0 };
+    (i0, i1, t)
+}
+
+// Point light shaped by an IES profile, with its axis aimed at `look_at`
+pub struct IesLight {
+    position: Point3,
+    frame: ONB,
+    profile: IesProfile,
+    color: Color,
+}
+
+impl IesLight {
+    // `color` tints and scales the profile's candela
+    pub fn new(position: Point3, look_at: &Point3, profile: IesProfile, color: Color) -> Self {
+        let frame = ONB::build_from_w(&(look_at - &position));
+        Self { position, frame, profile, color }
+    }
+}
+
+impl DeltaLight for IesLight {
+    fn sample(&self, p: &Point3) -> Option<(Vec3, Float, Color)> {
+        let to_light = &self.position - p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared <= 0.0 {
+            return None
+        }
+        let distance = distance_squared.sqrt();
+        let direction = to_light / distance;
+
+        let local = self.frame.to_local(&-&direction);
+        let vertical = local.z().clamp(-1.0, 1.0).acos().to_degrees();
+        let horizontal = local.y().atan2(local.x()).to_degrees();
+        let candela = self.profile.intensity(vertical, horizontal);
+        if candela <= 0.0 {
+            return None
+        }
+        Some((direction, distance, (PHOTOMETRIC_SCALE * candela / distance_squared) * &self.color))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Type C, quadrant symmetric. The TILT block has to be skipped and the
+    // candela get scaled by multiplier * ballast * ballast-lamp = 0.8.
+    const QUADRANT: &str = "IESNA:LM-63-2002
+[TEST] inline
+TILT=INCLUDE
+1
+2
+0 90
+1.0 0.5
+1 1000 2.0 3 2 1 2 0.5 0.5 0.1
+0.5 0.8 50
+0 45 90
+0 90
+100 80 0
+60 40 0
+";
+
+    fn assert_close(a: Float, b: Float, tolerance: Float) {
+        assert!((a - b).abs() <= tolerance * b.abs().max(1.0), "{a} != {b}");
+    }
+
+    #[test]
+    fn parse_skips_tilt_and_scales_candela() {
+        let profile = IesProfile::parse(QUADRANT).unwrap();
+        assert_close(profile.intensity(0.0, 0.0), 80.0, 1e-5);
+        assert_close(profile.intensity(45.0, 0.0), 64.0, 1e-5);
+        assert_close(profile.intensity(45.0, 90.0), 32.0, 1e-5);
+        assert_close(profile.intensity(90.0, 0.0), 0.0, 1e-5);
+    }
+
+    #[test]
+    fn quadrant_symmetry_folds_horizontal_angles() {
+        let profile = IesProfile::parse(QUADRANT).unwrap();
+        let at_45 = profile.intensity(45.0, 45.0);
+        assert_close(at_45, 48.0, 1e-5);
+        for horizontal in [135.0, 225.0, 315.0, -45.0] {
+            assert_close(profile.intensity(45.0, horizontal), at_45, 1e-5);
+        }
+        assert_close(profile.intensity(45.0, 180.0), profile.intensity(45.0, 0.0), 1e-5);
+        assert_close(profile.intensity(45.0, 270.0), profile.intensity(45.0, 90.0), 1e-5);
+    }
+
+    #[test]
+    fn vertical_angles_outside_the_profile_are_dark() {
+        let profile = IesProfile::parse(QUADRANT).unwrap();
+        assert_eq!(profile.intensity(120.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn parse_rejects_unsupported_files() {
+        assert!(IesProfile::parse("IESNA:LM-63-2002\n").is_err());
+        assert!(IesProfile::parse("TILT=lamp.tlt\n1 1000 1 1 1 1 2 0 0 0 1 1 0 0 0 100\n").is_err());
+        // Type A photometry
+        assert!(IesProfile::parse("TILT=NONE\n1 1000 1 1 1 3 2 0 0 0 1 1 0 0 0 100\n").is_err());
+        assert!(IesProfile::parse("TILT=NONE\n1 1000 1 3 1 1 2 0 0 0 1 1 0\n0 45\n").is_err());
+    }
+
+    #[test]
+    fn lumens_of_uniform_profile() {
+        let profile = IesProfile::new_uniform(100.0);
+        assert_close(profile.lumens(), 4.0*PI * 100.0, 5e-3);
+    }
+
+    #[test]
+    fn lumens_of_lambertian_profile() {
+        // I = 1000 cos(theta) over the lower hemisphere gives pi * 1000 lm
+        let angles: Vec<String> = (0..=90).map(|a| a.to_string()).collect();
+        let candela: Vec<String> = (0..=90)
+            .map(|a| (1000.0 * (a as Float).to_radians().cos()).to_string())
+            .collect();
+        let text = format!("TILT=NONE\n1 -1 1 91 1 1 2 0 0 0\n1 1 0\n{}\n0\n{}\n",
+                           angles.join(" "), candela.join(" "));
+        let profile = IesProfile::parse(&text).unwrap();
+        assert_close(profile.lumens(), PI * 1000.0, 5e-3);
+    }
+
+    #[test]
+    fn scale_to_lumens_matches_target() {
+        let mut profile = IesProfile::parse(QUADRANT).unwrap();
+        profile.scale_to_lumens(800.0);
+        assert_close(profile.lumens(), 800.0, 1e-3);
+    }
+}
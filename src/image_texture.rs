@@ -8,7 +8,10 @@ use stb_image::image::{LoadResult, load_with_depth};
 use std::path::Path;
 
 
-const BYTES_PER_PIXEL: usize = 3;
+// NOTE(srp): Images keep the file's own channels, grayscale ones have a
+// single channel and only images with 2 or 4 channels carry alpha
+const BYTES_PER_PIXEL: usize = 0;
+const COLOR_SCALE: Float = 1.0 / 255.0;
 
 pub struct ImageTexture {
     maybe_image: LoadResult
@@ -20,10 +23,8 @@ impl ImageTexture {
             maybe_image: load_with_depth(path, BYTES_PER_PIXEL, true)
         }
     }
-}
 
-impl Texture for ImageTexture {
-    fn value(&self, u: Float, v: Float, _p: &Point3) -> Color {
+    fn pixel(&self, u: Float, v: Float) -> Option<&[u8]> {
         match &self.maybe_image {
             LoadResult::ImageU8(img) => {
                 let u = u.clamp(0.0, 1.0);
@@ -31,14 +32,73 @@ impl Texture for ImageTexture {
                 let i = ((u * img.width as Float) as usize).min(img.width-1);
                 let j = ((v * img.height as Float) as usize).min(img.height-1);
 
-                const COLOR_SCALE: Float = 1.0 / 255.0;
                 let start = j*img.depth*img.width + i*img.depth;
-                let end = start + 3*img.depth;
-                let pixel = &img.data[start..end];
+                let end = start + img.depth;
+                Some(&img.data[start..end])
+            },
+            _ => None
+        }
+    }
+}
 
+impl Texture for ImageTexture {
+    fn value(&self, u: Float, v: Float, _p: &Point3) -> Color {
+        match self.pixel(u, v) {
+            Some(pixel) if pixel.len() < 3 => COLOR_SCALE * pixel[0] as Float * Color::one(),
+            Some(pixel) => {
                 COLOR_SCALE * Color::new(pixel[0] as Float, pixel[1] as Float, pixel[2] as Float)
             },
-            _ => Color::new(0.0, 1.0, 1.0) // Missing image cyan
+            None => Color::new(0.0, 1.0, 1.0) // Missing image cyan
+        }
+    }
+
+    // Images without an alpha channel are masks by their first channel
+    fn alpha(&self, u: Float, v: Float, _p: &Point3) -> Float {
+        match self.pixel(u, v) {
+            Some(pixel) if pixel.len() == 4 => COLOR_SCALE * pixel[3] as Float,
+            Some(pixel) if pixel.len() == 2 => COLOR_SCALE * pixel[1] as Float,
+            Some(pixel) => COLOR_SCALE * pixel[0] as Float,
+            None => 1.0 // Missing images stay visible
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2x1 image in a binary PNM file, `magic` picks grayscale (P5) or RGB (P6)
+    fn write_pnm(name: &str, magic: &str, pixels: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let mut bytes = format!("{magic}\n2 1\n255\n").into_bytes();
+        bytes.extend_from_slice(pixels);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn grayscale_mask_cuts_out_by_value() {
+        let path = write_pnm("rt_weekend_gray_mask.pgm", "P5", &[0, 255]);
+        let mask = ImageTexture::new(&path);
+        let p = Point3::new(0.0, 0.0, 0.0);
+        assert_eq!(mask.alpha(0.25, 0.5, &p), 0.0);
+        assert_eq!(mask.alpha(0.75, 0.5, &p), 1.0);
+        assert_eq!(mask.value(0.75, 0.5, &p).y(), 1.0);
+    }
+
+    #[test]
+    fn rgb_mask_cuts_out_by_first_channel() {
+        let path = write_pnm("rt_weekend_rgb_mask.ppm", "P6", &[0, 255, 255, 255, 0, 0]);
+        let mask = ImageTexture::new(&path);
+        let p = Point3::new(0.0, 0.0, 0.0);
+        assert_eq!(mask.alpha(0.25, 0.5, &p), 0.0);
+        assert_eq!(mask.alpha(0.75, 0.5, &p), 1.0);
+        assert_eq!(mask.value(0.25, 0.5, &p).z(), 1.0);
+    }
+
+    #[test]
+    fn missing_image_stays_visible() {
+        let mask = ImageTexture::new(Path::new("no_such_mask.png"));
+        assert_eq!(mask.alpha(0.5, 0.5, &Point3::new(0.0, 0.0, 0.0)), 1.0);
+    }
+}
@@ -1,3 +1,4 @@
+use super::float::{Float, FRAC_1_PI};
 use super::rng_float::RngGen;
 use super::vec3::Vec3;
 use super::color::Color;
@@ -26,6 +27,18 @@ impl Material for Isotropic {
     fn scatter(&self, r_in: &Ray, hit: &HitRecord, rng: &mut RngGen) -> Scatter {
         let scattered = Ray::new(hit.p(), &Vec3::random_sphere(rng), r_in.time());
         let attenuation = self.albedo.value(hit.u(), hit.v(), hit.p());
-        Scatter::Some(scattered, attenuation)
+        Scatter::Some(scattered, attenuation, 0.25 * FRAC_1_PI)
+    }
+
+    fn eval(&self, _r_in: &Ray, hit: &HitRecord, _scattered: &Vec3) -> Color {
+        0.25 * FRAC_1_PI * self.albedo.value(hit.u(), hit.v(), hit.p())
+    }
+
+    fn pdf(&self, _r_in: &Ray, _hit: &HitRecord, _scattered: &Vec3) -> Float {
+        0.25 * FRAC_1_PI
+    }
+
+    fn has_non_specular(&self) -> bool {
+        true
     }
 }
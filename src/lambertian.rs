@@ -1,3 +1,4 @@
+use super::float::{Float, FRAC_1_PI};
 use super::rng_float::RngGen;
 use super::material::{Material, Scatter};
 use super::ray::Ray;
@@ -32,6 +33,23 @@ impl Material for Lambertian {
 
         let new_ray = Ray::new(hit.p(), &dir, r_in.time());
         let new_color = self.albedo.value(hit.u(), hit.v(), hit.p());
-        return Scatter::Some(new_ray, new_color)
+        let pdf = self.pdf(r_in, hit, &dir.unit_vector());
+        return Scatter::Some(new_ray, new_color, pdf)
+    }
+
+    fn eval(&self, _r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Color {
+        let cosine = Vec3::dot(hit.normal(), scattered);
+        if cosine <= 0.0 {
+            return Color::zero()
+        }
+        cosine * FRAC_1_PI * self.albedo.value(hit.u(), hit.v(), hit.p())
+    }
+
+    fn pdf(&self, _r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Float {
+        Vec3::dot(hit.normal(), scattered).max(0.0) * FRAC_1_PI
+    }
+
+    fn has_non_specular(&self) -> bool {
+        true
     }
 }
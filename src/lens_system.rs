@@ -0,0 +1,327 @@
+use super::float::Float;
+use super::vec3::Vec3;
+type Point3 = Vec3;
+
+use std::path::Path;
+
+// One interface of a lens prescription, lengths in mm. Elements go from the
+// scene towards the film.
+#[derive(Clone, Copy)]
+pub struct LensElement {
+    radius: Float, // Curvature radius, 0 for the aperture stop
+    thickness: Float, // Distance to the next element along the axis
+    ior: Float, // Of what comes after the interface, 0 or 1 for air
+    aperture_radius: Float,
+}
+
+impl LensElement {
+    // `aperture` is a diameter, like in prescription tables
+    pub fn new(radius: Float, thickness: Float, ior: Float, aperture: Float) -> Self {
+        Self { radius, thickness, ior, aperture_radius: 0.5 * aperture }
+    }
+
+    fn is_stop(&self) -> bool {
+        self.radius == 0.0
+    }
+}
+
+// Sequence of spherical lens elements, traced like pbrt's RealisticCamera.
+// Lens space has the film at z = 0 and the scene towards +z.
+#[derive(Clone)]
+pub struct LensSystem {
+    elements: Vec<LensElement>,
+}
+
+impl LensSystem {
+    pub fn new(path: &Path) -> Self {
+        let parsed = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Self::parse(&text));
+        match parsed {
+            Ok(lens) => lens,
+            Err(e) => {
+                eprintln!("Couldn't load lens {}: {}", path.display(), e);
+                Self::double_gauss_50mm()
+            }
+        }
+    }
+
+    pub fn from_elements(elements: Vec<LensElement>) -> Self {
+        Self { elements }
+    }
+
+    // Rows of radius, thickness, ior and aperture diameter, `#` starts a comment
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut elements = Vec::new();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue
+            }
+            let row = line.split_whitespace()
+                .map(|token| token.parse::<Float>().map_err(|_| format!("bad number '{token}'")))
+                .collect::<Result<Vec<_>, _>>()?;
+            if row.len() != 4 {
+                return Err(format!("expected 4 numbers per element, found {}", row.len()))
+            }
+            elements.push(LensElement::new(row[0], row[1], row[2], row[3]));
+        }
+        if elements.is_empty() {
+            return Err("no elements".to_string())
+        }
+        Ok(Self { elements })
+    }
+
+    // D-GAUSS F/2 22deg HFOV, US patent 2,673,491 (Tronnier), scaled to 50mm
+    pub fn double_gauss_50mm() -> Self {
+        Self::from_elements(vec![
+            LensElement::new(29.475, 3.76, 1.67, 25.2),
+            LensElement::new(84.83, 0.12, 1.0, 25.2),
+            LensElement::new(19.275, 4.025, 1.67, 23.0),
+            LensElement::new(40.77, 3.275, 1.699, 23.0),
+            LensElement::new(12.75, 5.705, 1.0, 18.0),
+            LensElement::new(0.0, 4.5, 0.0, 17.1),
+            LensElement::new(-14.495, 1.18, 1.603, 17.0),
+            LensElement::new(40.77, 6.065, 1.658, 20.0),
+            LensElement::new(-20.385, 0.19, 1.0, 20.0),
+            LensElement::new(437.065, 3.22, 1.717, 20.0),
+            LensElement::new(-39.73, 0.0, 1.0, 20.0),
+        ])
+    }
+
+    // Stops the lens down, the stop can't open wider than in the prescription
+    pub fn aperture_diameter(&mut self, diameter: Float) -> &mut Self {
+        for element in self.elements.iter_mut().filter(|e| e.is_stop()) {
+            element.aperture_radius = element.aperture_radius.min(0.5 * diameter);
+        }
+        self
+    }
+
+    pub fn rear_z(&self) -> Float {
+        self.elements[self.elements.len() - 1].thickness
+    }
+
+    pub fn rear_aperture_radius(&self) -> Float {
+        self.elements[self.elements.len() - 1].aperture_radius
+    }
+
+    fn front_z(&self) -> Float {
+        self.elements.iter().map(|e| e.thickness).sum()
+    }
+
+    // Copy of the lens moved so objects `focus_distance` mm away from the
+    // film are sharp. Fails for lenses that block or don't bend paraxial rays.
+    pub fn focused(&self, focus_distance: Float, film_diagonal: Float) -> Result<Self, String> {
+        let (pz, fz) = self.thick_lens_approximation(film_diagonal)?;
+        let f = fz[0] - pz[0];
+        let z = -focus_distance;
+        let c = (pz[1] - z - pz[0]) * (pz[1] - z - 4.0*f - pz[0]);
+        let delta = 0.5 * (pz[1] - z + pz[0] - c.max(0.0).sqrt());
+        let mut lens = self.clone();
+        let last = lens.elements.len() - 1;
+        lens.elements[last].thickness = (lens.elements[last].thickness + delta).max(0.0);
+        Ok(lens)
+    }
+
+    // Principal planes and focal points, for rays entering from the scene
+    // (index 0) and from the film (index 1)
+    fn thick_lens_approximation(&self, film_diagonal: Float) -> Result<([Float; 2], [Float; 2]), String> {
+        // NOTE(srp): Close enough to the axis to be paraxial, far enough for
+        // f32 to tell it apart from it.
+        let x = 0.01 * film_diagonal;
+        let scene_origin = Point3::new(x, 0.0, self.front_z() + 1.0);
+        let (origin, direction) = self.trace_from_scene(&scene_origin, &Vec3::new(0.0, 0.0, -1.0))
+            .ok_or("a paraxial ray from the scene doesn't get through")?;
+        let (pz0, fz0) = cardinal_points(&scene_origin, &origin, &direction)?;
+
+        let film_origin = Point3::new(x, 0.0, self.rear_z() - 1.0);
+        let (origin, direction) = self.trace_from_film(&film_origin, &Vec3::new(0.0, 0.0, 1.0))
+            .ok_or("a paraxial ray from the film doesn't get through")?;
+        let (pz1, fz1) = cardinal_points(&film_origin, &origin, &direction)?;
+
+        Ok(([pz0, pz1], [fz0, fz1]))
+    }
+
+    // Ray leaving the front of the lens, None if the lens blocks it
+    pub fn trace_from_film(&self, origin: &Point3, direction: &Vec3) -> Option<(Point3, Vec3)> {
+        // NOTE(srp): Elements are laid out along -z here, like pbrt does
+        let mut o = flip_z(origin);
+        let mut d = flip_z(direction);
+        let mut element_z = 0.0;
+        for (i, element) in self.elements.iter().enumerate().rev() {
+            element_z -= element.thickness;
+            let (t, normal) = intersect_element(element, element_z, &o, &d)?;
+            o = &o + t*&d;
+            if o.x()*o.x() + o.y()*o.y() > element.aperture_radius*element.aperture_radius {
+                return None
+            }
+            if let Some(normal) = normal {
+                let eta_i = air_if_zero(element.ior);
+                let eta_t = if i > 0 { air_if_zero(self.elements[i - 1].ior) } else { 1.0 };
+                d = refract(&d, &normal, eta_i / eta_t)?;
+            }
+        }
+        Some((flip_z(&o), flip_z(&d)))
+    }
+
+    fn trace_from_scene(&self, origin: &Point3, direction: &Vec3) -> Option<(Point3, Vec3)> {
+        let mut o = flip_z(origin);
+        let mut d = flip_z(direction);
+        let mut element_z = -self.front_z();
+        for (i, element) in self.elements.iter().enumerate() {
+            let (t, normal) = intersect_element(element, element_z, &o, &d)?;
+            o = &o + t*&d;
+            if o.x()*o.x() + o.y()*o.y() > element.aperture_radius*element.aperture_radius {
+                return None
+            }
+            if let Some(normal) = normal {
+                let eta_i = if i > 0 { air_if_zero(self.elements[i - 1].ior) } else { 1.0 };
+                let eta_t = air_if_zero(element.ior);
+                d = refract(&d, &normal, eta_i / eta_t)?;
+            }
+            element_z += element.thickness;
+        }
+        Some((flip_z(&o), flip_z(&d)))
+    }
+}
+
+fn flip_z(v: &Vec3) -> Vec3 {
+    Vec3::new(v.x(), v.y(), -v.z())
+}
+
+fn air_if_zero(ior: Float) -> Float {
+    if ior == 0.0 { 1.0 } else { ior }
+}
+
+// Distance to the element and the normal facing the ray, no normal for the stop
+fn intersect_element(element: &LensElement, element_z: Float,
+                     o: &Point3, d: &Vec3) -> Option<(Float, Option<Vec3>)> {
+    if element.is_stop() {
+        if d.z() == 0.0 {
+            return None
+        }
+        let t = (element_z - o.z()) / d.z();
+        return if t >= 0.0 { Some((t, None)) } else { None }
+    }
+
+    let radius = element.radius;
+    let oc = o - &Vec3::new(0.0, 0.0, element_z + radius);
+    let a = d.length_squared();
+    let half_b = Vec3::dot(d, &oc);
+    let c = oc.length_squared() - radius*radius;
+    let discriminant = half_b*half_b - a*c;
+    if discriminant < 0.0 {
+        return None
+    }
+    let sqrtd = discriminant.sqrt();
+    let (t0, t1) = ((-half_b - sqrtd) / a, (-half_b + sqrtd) / a);
+    // Which of the two intersections is the lens surface
+    let use_closer = (d.z() > 0.0) ^ (radius < 0.0);
+    let t = if use_closer { t0.min(t1) } else { t0.max(t1) };
+    if t < 0.0 {
+        return None
+    }
+    let normal = (oc + t*d).unit_vector();
+    let normal = if Vec3::dot(&normal, d) > 0.0 { -normal } else { normal };
+    Some((t, Some(normal)))
+}
+
+// `normal` faces against `d`, None on total internal reflection
+fn refract(d: &Vec3, normal: &Vec3, eta: Float) -> Option<Vec3> {
+    let wi = -d.unit_vector();
+    let cos_i = Vec3::dot(normal, &wi);
+    let sin2_i = (1.0 - cos_i*cos_i).max(0.0);
+    let sin2_t = eta*eta*sin2_i;
+    if sin2_t >= 1.0 {
+        return None
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-eta*&wi + (eta*cos_i - cos_t)*normal)
+}
+
+// Principal plane and focal point along z for a ray parallel to the axis
+fn cardinal_points(in_origin: &Point3, out_origin: &Point3,
+                   out_direction: &Vec3) -> Result<(Float, Float), String> {
+    if out_direction.x() == 0.0 {
+        return Err("the lens doesn't bend paraxial rays".to_string())
+    }
+    let t_focus = -out_origin.x() / out_direction.x();
+    let fz = -(out_origin.z() + t_focus*out_direction.z());
+    let t_principal = (in_origin.x() - out_origin.x()) / out_direction.x();
+    let pz = -(out_origin.z() + t_principal*out_direction.z());
+    Ok((pz, fz))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Where a ray crosses the axis, in lens space
+    fn axis_crossing(origin: &Point3, direction: &Vec3) -> Float {
+        origin.z() - origin.x() * direction.z() / direction.x()
+    }
+
+    #[test]
+    fn parse_skips_comments_and_blank_lines() {
+        let lens = LensSystem::parse("
+            # radius thickness ior aperture
+            29.475 3.76 1.67 25.2   # front
+            0      4.5  0    17.1
+
+            -39.73 2.0  1    20.0
+        ").unwrap();
+        assert_eq!(lens.elements.len(), 3);
+        assert!(lens.elements[1].is_stop());
+        assert_eq!(lens.rear_z(), 2.0);
+        assert_eq!(lens.rear_aperture_radius(), 10.0);
+    }
+
+    #[test]
+    fn parse_rejects_bad_rows() {
+        assert!(LensSystem::parse("29.475 3.76 1.67\n").is_err());
+        assert!(LensSystem::parse("29.475 3.76 1.67 25.2 1.0\n").is_err());
+        assert!(LensSystem::parse("29.475 3.76 glass 25.2\n").is_err());
+        assert!(LensSystem::parse("# only a comment\n\n").is_err());
+    }
+
+    #[test]
+    fn aperture_only_stops_down() {
+        let mut lens = LensSystem::double_gauss_50mm();
+        lens.aperture_diameter(100.0);
+        assert_eq!(lens.elements[5].aperture_radius, 8.55);
+        lens.aperture_diameter(5.0);
+        assert_eq!(lens.elements[5].aperture_radius, 2.5);
+    }
+
+    #[test]
+    fn double_gauss_is_a_50mm_lens() {
+        let (pz, fz) = LensSystem::double_gauss_50mm().thick_lens_approximation(43.27).unwrap();
+        let focal_length = fz[0] - pz[0];
+        assert!((focal_length.abs() - 50.0).abs() < 1.5, "focal length {focal_length}");
+    }
+
+    #[test]
+    fn focused_at_infinity_images_parallel_rays_on_the_film() {
+        let lens = LensSystem::double_gauss_50mm().focused(1.0e6, 43.27).unwrap();
+        let origin = Point3::new(0.5, 0.0, lens.front_z() + 1.0);
+        let (o, d) = lens.trace_from_scene(&origin, &Vec3::new(0.0, 0.0, -1.0)).unwrap();
+        let crossing = axis_crossing(&o, &d);
+        assert!(crossing.abs() < 0.1, "rays meet {crossing} mm from the film");
+    }
+
+    #[test]
+    fn focusing_closer_moves_the_lens_out() {
+        let lens = LensSystem::double_gauss_50mm();
+        let far = lens.focused(1.0e5, 43.27).unwrap();
+        let near = lens.focused(500.0, 43.27).unwrap();
+        assert!(near.rear_z() > far.rear_z());
+    }
+
+    #[test]
+    fn focused_fails_without_refraction() {
+        let stop_only = LensSystem::from_elements(vec![LensElement::new(0.0, 10.0, 0.0, 10.0)]);
+        assert!(stop_only.focused(1000.0, 43.27).is_err());
+        let pinhole = LensSystem::from_elements(vec![LensElement::new(0.0, 10.0, 0.0, 0.0)]);
+        assert!(pinhole.focused(1000.0, 43.27).is_err());
+    }
+}
@@ -30,6 +30,25 @@ pub mod translate;
 pub mod rotate_y;
 pub mod convex_constant_medium;
 pub mod isotropic;
+pub mod onb;
+pub mod curve;
+pub mod microfacet;
+pub mod fresnel;
+pub mod conductor;
+pub mod principled;
+pub mod mix_material;
+pub mod coated;
+pub mod normal_map;
+pub mod alpha_mask;
+pub mod environment_map;
+pub mod physical_sky;
+pub mod delta_light;
+pub mod ies;
+pub mod distribution;
+pub mod aperture;
+pub mod lens_system;
+pub mod camera_animation;
+pub mod spin_z;
 
 use float::*;
 use vec3::Vec3;
@@ -37,7 +56,8 @@ use rng_float::RngGen;
 use pixel_buffer::PixelBuffer;
 use default_scenes::{
     DefaultScene, select_default_scene, select_default_scene_cam_settings,
-    select_default_scene_sky
+    select_default_scene_sky, select_default_scene_lights, select_default_scene_light_scale,
+    select_default_scene_animation
 };
 use renderer::RenderInfo;
 
@@ -55,21 +75,36 @@ fn main() {
     const ASPECT_RATIO:Float = 1.0;
     const IMAGE_WIDTH:usize = 200;
     const SAMPLES_PER_PIXEL:i32 = 100;
-    const MAX_DEPTH: i32 = 5;
+    // Russian roulette ends the paths, this only stops ones stuck bouncing
+    const MAX_DEPTH: i32 = 1000;
 
     // Pixel Buffer
     let buffer_lock = Arc::new(RwLock::new(PixelBuffer::new(IMAGE_WIDTH, ASPECT_RATIO)));
 
-    // Scene, World, Sky and Camera Settings
+    // Scene, World, Sky, Lights and Camera Settings
     let ref scene = DefaultScene::FinalSceneBook2;
     let world = Rc::new(select_default_scene(scene, &mut rng));
     let sky = select_default_scene_sky(scene);
+    let lights = select_default_scene_lights(scene);
+    let light_scale = select_default_scene_light_scale(scene);
     let mut cam_settings = select_default_scene_cam_settings(scene);
 
+    // Camera Animation, None renders a single image
+    let animation = select_default_scene_animation(scene)
+        .map(|animation| {
+            let frames = animation.frame_range();
+            (animation, frames)
+        });
+    if let Some((ref animation, ref frames)) = animation {
+        animation.apply(frames.start, &mut cam_settings);
+    }
+
     // Render Info
     let mut rend = RenderInfo::new(buffer_lock.clone(), &mut cam_settings);
     rend.sky(sky);
     rend.world(world);
+    rend.delta_lights(lights);
+    rend.light_scale(light_scale);
 
     // Fast Render Pass for Preview
     eprintln!("Making a fast render pass for preview");
@@ -81,12 +116,30 @@ fn main() {
     // Render
     let timer_start = std::time::Instant::now();
     eprintln!("\nGetting serious now >:)\n");
-    rend.render(SAMPLES_PER_PIXEL, MAX_DEPTH, &mut rng);
-    let timer_duration = timer_start.elapsed(); 
+    match animation {
+        None => {
+            rend.render(SAMPLES_PER_PIXEL, MAX_DEPTH, &mut rng);
+            eprint!("\nWriting output.\n");
+            let ppm_output = buffer_lock.read().unwrap().to_ppm();
+            print!("{ppm_output}");
+        },
+        Some((animation, frames)) => {
+            // Frames go to numbered files in the working directory
+            for frame in frames {
+                eprintln!("\nRendering frame {frame}\n");
+                animation.apply(frame, &mut cam_settings);
+                rend.camera(&mut cam_settings);
+                rend.render(SAMPLES_PER_PIXEL, MAX_DEPTH, &mut rng);
 
-    eprint!("\nWriting output.\n");
-    let ppm_output = buffer_lock.read().unwrap().to_ppm();
-    print!("{ppm_output}");
+                let path = format!("frame_{frame:04}.ppm");
+                let ppm_output = buffer_lock.read().unwrap().to_ppm();
+                if let Err(e) = std::fs::write(&path, ppm_output) {
+                    eprintln!("Couldn't write {path}: {e}");
+                }
+            }
+        }
+    }
+    let timer_duration = timer_start.elapsed(); 
     eprint!("\nDone.\nRendering took {:?}\n", timer_duration);
 
     preview_thread.join().unwrap();
@@ -1,6 +1,5 @@
 use super::float::Float;
 use super::vec3::Vec3;
-type Point3 = Vec3;
 use super::ray::Ray;
 use super::color::Color;
 use super::hittable::HitRecord;
@@ -9,13 +8,38 @@ use super::rng_float::RngGen;
 pub trait Material {
     fn scatter(&self, r_in: &Ray, hit: &HitRecord, rng: &mut RngGen) -> Scatter;
 
-    fn emmited(&self, _u: Float, _v: Float, _p: &Point3) -> Color {
+    // Radiance leaving `hit` back along `r_in`
+    fn emmited(&self, _r_in: &Ray, _hit: &HitRecord) -> Color {
         Color::zero()
     }
+
+    // BSDF times the cosine term for the unit direction `scattered`.
+    // Specular lobes can't be evaluated and are left out.
+    fn eval(&self, _r_in: &Ray, _hit: &HitRecord, _scattered: &Vec3) -> Color {
+        Color::zero()
+    }
+
+    // Solid angle density with which `scatter` samples the unit direction
+    // `scattered`, specular lobes are left out.
+    fn pdf(&self, _r_in: &Ray, _hit: &HitRecord, _scattered: &Vec3) -> Float {
+        0.0
+    }
+
+    // Whether the renderer should sample objects made of this as lights
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    // Whether some lobe isn't specular, so `eval` can be non zero and lights
+    // are worth sampling from it
+    fn has_non_specular(&self) -> bool {
+        false
+    }
 }
 
 pub enum Scatter {
-    Some(Ray, Color),
+    Some(Ray, Color, Float), // Ray, weight and solid angle pdf of the ray
+    Specular(Ray, Color), // Sampled from a delta lobe, can't be eval'd
     None
 }
 
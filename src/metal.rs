@@ -23,7 +23,7 @@ impl Material for Metal {
         let fuzz_offset = self.fuzz * &Vec3::random_sphere(rng);
         let new_ray = Ray::new(hit.p(), &(&reflect + fuzz_offset), r_in.time());
         return if Vec3::dot(new_ray.direction(), hit.normal()) > 0.0 {
-            Scatter::Some(new_ray, self.albedo.copy())
+            Scatter::Specular(new_ray, self.albedo.copy())
         } else {
             Scatter::None
         }
@@ -0,0 +1,124 @@
+use super::float::{Float, PI};
+use super::vec3::Vec3;
+
+// Trowbridge-Reitz (GGX) microfacet distribution with Smith shadowing-masking.
+// NOTE(srp): Every direction here is in the local shading frame, where the
+// macrosurface normal is +z (see ONB::to_local).
+pub struct GGX {
+    alpha: Float,
+}
+
+impl GGX {
+    const MIN_ALPHA: Float = 1e-3;
+
+    // Perceptual roughness in [0, 1], remapped as alpha = roughness^2
+    pub fn new(roughness: Float) -> Self {
+        let roughness = roughness.clamp(0.0, 1.0);
+        Self { alpha: (roughness*roughness).max(Self::MIN_ALPHA) }
+    }
+
+    pub fn is_smooth(&self) -> bool {
+        self.alpha <= Self::MIN_ALPHA
+    }
+
+    pub fn d(&self, m: &Vec3) -> Float {
+        if m.z() <= 0.0 { return 0.0 }
+        let a2 = self.alpha*self.alpha;
+        let t = (m.x()*m.x() + m.y()*m.y()) / a2 + m.z()*m.z();
+        (PI * a2 * t * t).recip()
+    }
+
+    pub fn lambda(&self, w: &Vec3) -> Float {
+        let cos2 = w.z()*w.z();
+        if cos2 == 0.0 { return Float::INFINITY }
+        let tan2 = (w.x()*w.x() + w.y()*w.y()) / cos2;
+        0.5 * (-1.0 + (1.0 + self.alpha*self.alpha*tan2).sqrt())
+    }
+
+    pub fn g1(&self, w: &Vec3) -> Float {
+        (1.0 + self.lambda(w)).recip()
+    }
+
+    pub fn g2(&self, wo: &Vec3, wi: &Vec3) -> Float {
+        (1.0 + self.lambda(wo) + self.lambda(wi)).recip()
+    }
+
+    // Density of the normals visible from `wo`
+    pub fn d_visible(&self, wo: &Vec3, m: &Vec3) -> Float {
+        if wo.z() == 0.0 { return 0.0 }
+        self.g1(wo) * Vec3::dot(wo, m).abs() * self.d(m) / wo.z().abs()
+    }
+
+    // Half vector, D*G2/(4*cos_o) and the density of sampling `wi` from `wo`
+    // through a reflection on a visible normal.
+    pub fn reflection(&self, wo: &Vec3, wi: &Vec3) -> Option<(Vec3, Float, Float)> {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return None
+        }
+        let m = wo + wi;
+        if m.near_zero() {
+            return None
+        }
+        let m = m.unit_vector();
+        let cos_o = Vec3::dot(wo, &m);
+        if cos_o <= 0.0 {
+            return None
+        }
+        let f_cos = self.d(&m) * self.g2(wo, wi) / (4.0 * wo.z());
+        let pdf = self.d_visible(wo, &m) / (4.0 * cos_o);
+        Some((m, f_cos, pdf))
+    }
+
+    // Same as reflection() for a refraction through a visible normal,
+    // following Walter et al. 2007 without the Fresnel term.
+    // NOTE(srp): refraction_ratio := $\frac{\eta_i}{\eta_t}$
+    pub fn transmission(&self, wo: &Vec3, wi: &Vec3, refraction_ratio: Float)
+            -> Option<(Vec3, Float, Float)> {
+        if wo.z() <= 0.0 || wi.z() >= 0.0 {
+            return None
+        }
+        let m = -(refraction_ratio*wo + wi);
+        if m.near_zero() {
+            return None
+        }
+        let m = m.unit_vector();
+        let m = if m.z() < 0.0 { -m } else { m };
+        let cos_o = Vec3::dot(wo, &m);
+        let cos_i = Vec3::dot(wi, &m);
+        if cos_o <= 0.0 || cos_i >= 0.0 {
+            return None
+        }
+        let denom = refraction_ratio*cos_o + cos_i;
+        if denom == 0.0 {
+            return None
+        }
+        let jacobian = cos_i.abs() / (denom*denom);
+        let f_cos = self.d(&m) * self.g2(wo, wi) * cos_o * jacobian / wo.z();
+        let pdf = self.d_visible(wo, &m) * jacobian;
+        Some((m, f_cos, pdf))
+    }
+
+    // Heitz 2018, "Sampling the GGX Distribution of Visible Normals"
+    pub fn sample_visible_normal(&self, wo: &Vec3, u1: Float, u2: Float) -> Vec3 {
+        let wo = if wo.z() < 0.0 { -wo } else { wo.copy() };
+        let vh = Vec3::new(self.alpha*wo.x(), self.alpha*wo.y(), wo.z()).unit_vector();
+
+        let lensq = vh.x()*vh.x() + vh.y()*vh.y();
+        let t1 = if lensq > 0.0 {
+            Vec3::new(-vh.y(), vh.x(), 0.0) / lensq.sqrt()
+        } else {
+            Vec3::right()
+        };
+        let t2 = Vec3::cross(&vh, &t1);
+
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let p2 = r * phi.sin();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s)*(1.0 - p1*p1).sqrt() + s*p2;
+
+        let nh = p1*&t1 + p2*&t2 + (1.0 - p1*p1 - p2*p2).max(0.0).sqrt()*&vh;
+        Vec3::new(self.alpha*nh.x(), self.alpha*nh.y(), nh.z().max(1e-6)).unit_vector()
+    }
+}
@@ -0,0 +1,74 @@
+use super::float::Float;
+use super::vec3::Vec3;
+type Point3 = Vec3;
+use super::rng_float::RngGen;
+use super::material::{Material, Scatter};
+use super::ray::Ray;
+use super::hittable::HitRecord;
+use super::color::Color;
+use super::texture::{Texture, SolidColor};
+
+use std::rc::Rc;
+
+// Blends two materials, the first channel of `mask` is the amount of `b`.
+// Each scatter picks one of the materials with that probability.
+pub struct MixMaterial {
+    a: Rc<dyn Material>,
+    b: Rc<dyn Material>,
+    mask: Rc<dyn Texture>,
+}
+
+impl MixMaterial {
+    pub fn new(a: Rc<dyn Material>, b: Rc<dyn Material>, mask: Rc<dyn Texture>) -> Self {
+        Self { a, b, mask }
+    }
+
+    pub fn new_from_amount(a: Rc<dyn Material>, b: Rc<dyn Material>, amount: Float) -> Self {
+        Self::new(a, b, Rc::new(SolidColor::new_rgb(amount, amount, amount)))
+    }
+
+    fn amount(&self, u: Float, v: Float, p: &Point3) -> Float {
+        self.mask.value(u, v, p).x().clamp(0.0, 1.0)
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(&self, r_in: &Ray, hit: &HitRecord, rng: &mut RngGen) -> Scatter {
+        let scatter = if self.amount(hit.u(), hit.v(), hit.p()) > rng.get() {
+            self.b.scatter(r_in, hit, rng)
+        } else {
+            self.a.scatter(r_in, hit, rng)
+        };
+        // The direction could have been sampled by either material
+        match scatter {
+            Scatter::Some(new_ray, attenuation, _) => {
+                let pdf = self.pdf(r_in, hit, &new_ray.direction().unit_vector());
+                Scatter::Some(new_ray, attenuation, pdf)
+            },
+            other => other
+        }
+    }
+
+    fn eval(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Color {
+        let amount = self.amount(hit.u(), hit.v(), hit.p());
+        Color::lerp(&self.a.eval(r_in, hit, scattered), &self.b.eval(r_in, hit, scattered), amount)
+    }
+
+    fn pdf(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Float {
+        let amount = self.amount(hit.u(), hit.v(), hit.p());
+        (1.0 - amount)*self.a.pdf(r_in, hit, scattered) + amount*self.b.pdf(r_in, hit, scattered)
+    }
+
+    fn emmited(&self, r_in: &Ray, hit: &HitRecord) -> Color {
+        let amount = self.amount(hit.u(), hit.v(), hit.p());
+        Color::lerp(&self.a.emmited(r_in, hit), &self.b.emmited(r_in, hit), amount)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.a.is_emissive() || self.b.is_emissive()
+    }
+
+    fn has_non_specular(&self) -> bool {
+        self.a.has_non_specular() || self.b.has_non_specular()
+    }
+}
@@ -45,6 +45,13 @@ impl MovingSphere {
         *out_u = phi * FRAC_1_PI * 0.5;
         *out_v = theta * FRAC_1_PI;
     }
+
+    fn get_tangents(p: &Point3, radius: Float) -> (Vec3, Vec3) {
+        let sin_theta = (1.0 - p.y()*p.y()).max(1e-8).sqrt();
+        let dpdu = 2.0 * PI * radius * Vec3::new(p.z(), 0.0, -p.x());
+        let dpdv = PI * radius * Vec3::new(-p.x()*p.y()/sin_theta, sin_theta, -p.y()*p.z()/sin_theta);
+        (dpdu, dpdv)
+    }
 }
 
 impl Hittable for MovingSphere {
@@ -76,7 +83,9 @@ impl Hittable for MovingSphere {
         let mut u: Float = 0.0;
         let mut v: Float = 0.0;
         MovingSphere::get_uv(&outward_normal, &mut u, &mut v);
-        let rec = HitRecord::new(&r, outward_normal, rec_mat, rec_t, u, v);
+        let (dpdu, dpdv) = MovingSphere::get_tangents(&outward_normal, self.radius());
+        let mut rec = HitRecord::new(&r, outward_normal, rec_mat, rec_t, u, v);
+        rec.set_tangents(dpdu, dpdv);
         Some(rec)
     }
 
@@ -0,0 +1,155 @@
+use super::float::Float;
+use super::rng_float::RngGen;
+use super::vec3::Vec3;
+use super::ray::Ray;
+use super::hittable::{Hittable, HitRecord};
+use super::hittable_list::HittableList;
+use super::texture::Texture;
+use super::aabb::AABB;
+
+use std::rc::Rc;
+
+// Perturbs the shading normal of `source` with a tangent space normal map,
+// where the red, green and blue channels follow dpdu, dpdv and the normal.
+pub struct NormalMap {
+    source: Rc<dyn Hittable>,
+    map: Rc<dyn Texture>,
+}
+
+impl NormalMap {
+    pub fn new(source: Rc<dyn Hittable>, map: Rc<dyn Texture>) -> Self {
+        Self { source, map }
+    }
+}
+
+impl Hittable for NormalMap {
+    fn hit(&self, r:&Ray, t_min:Float, t_max:Float, rng: &mut RngGen) -> Option<HitRecord> {
+        let mut hit = self.source.hit(r, t_min, t_max, rng)?;
+        if hit.dpdu().near_zero() || hit.dpdv().near_zero() {
+            return Some(hit)
+        }
+
+        let n = outward_normal(&hit);
+        let tangent = (hit.dpdu() - Vec3::dot(hit.dpdu(), &n)*&n).unit_vector();
+        let mut bitangent = Vec3::cross(&n, &tangent);
+        if Vec3::dot(&bitangent, hit.dpdv()) < 0.0 {
+            bitangent = -bitangent;
+        }
+
+        let c = self.map.value(hit.u(), hit.v(), hit.p());
+        let local = 2.0*c - Vec3::one();
+        let shading_normal = local.x()*&tangent + local.y()*&bitangent + local.z()*&n;
+        if shading_normal.near_zero() {
+            return Some(hit)
+        }
+        hit.set_shading_normal(shading_normal.unit_vector());
+        Some(hit)
+    }
+
+    fn bounding_box(&self, time0: Float, time1: Float) -> Option<AABB> {
+        self.source.bounding_box(time0, time1)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.source.is_emissive()
+    }
+
+    // Lights inside keep the same map
+    fn collect_lights(&self, lights: &mut HittableList) {
+        let mut inside = HittableList::new();
+        self.source.collect_lights(&mut inside);
+        for light in inside.objects() {
+            lights.add(Rc::new(NormalMap::new(light.clone(), self.map.clone())));
+        }
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        self.source.pdf_value(origin, direction, rng)
+    }
+
+    fn random(&self, origin: &Vec3, rng: &mut RngGen) -> Vec3 {
+        self.source.random(origin, rng)
+    }
+}
+
+// Perturbs the shading normal of `source` as if it was displaced along its
+// normal by the first channel of `bump` times `scale`.
+pub struct BumpMap {
+    source: Rc<dyn Hittable>,
+    bump: Rc<dyn Texture>,
+    scale: Float,
+}
+
+impl BumpMap {
+    pub fn new(source: Rc<dyn Hittable>, bump: Rc<dyn Texture>, scale: Float) -> Self {
+        Self { source, bump, scale }
+    }
+
+    fn displacement(&self, u: Float, v: Float, p: &Vec3) -> Float {
+        self.scale * self.bump.value(u, v, p).x()
+    }
+}
+
+impl Hittable for BumpMap {
+    fn hit(&self, r:&Ray, t_min:Float, t_max:Float, rng: &mut RngGen) -> Option<HitRecord> {
+        let mut hit = self.source.hit(r, t_min, t_max, rng)?;
+        if hit.dpdu().near_zero() || hit.dpdv().near_zero() {
+            return Some(hit)
+        }
+
+        // NOTE(srp): There are no ray differentials, so finite differences
+        // use a fixed step in (u, v).
+        const DELTA: Float = 0.0005;
+        let (u, v, p) = (hit.u(), hit.v(), hit.p());
+        let displace = self.displacement(u, v, p);
+        let u_displace = self.displacement(u + DELTA, v, &(p + DELTA*hit.dpdu()));
+        let v_displace = self.displacement(u, v + DELTA, &(p + DELTA*hit.dpdv()));
+
+        let n = outward_normal(&hit);
+        let dpdu = hit.dpdu() + ((u_displace - displace) / DELTA)*&n;
+        let dpdv = hit.dpdv() + ((v_displace - displace) / DELTA)*&n;
+        let mut shading_normal = Vec3::cross(&dpdu, &dpdv);
+        if shading_normal.near_zero() {
+            return Some(hit)
+        }
+        if Vec3::dot(&shading_normal, &n) < 0.0 {
+            shading_normal = -shading_normal;
+        }
+        hit.set_shading_normal(shading_normal.unit_vector());
+        hit.set_tangents(dpdu, dpdv);
+        Some(hit)
+    }
+
+    fn bounding_box(&self, time0: Float, time1: Float) -> Option<AABB> {
+        self.source.bounding_box(time0, time1)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.source.is_emissive()
+    }
+
+    // Lights inside keep the same bumps
+    fn collect_lights(&self, lights: &mut HittableList) {
+        let mut inside = HittableList::new();
+        self.source.collect_lights(&mut inside);
+        for light in inside.objects() {
+            lights.add(Rc::new(BumpMap::new(light.clone(), self.bump.clone(), self.scale)));
+        }
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        self.source.pdf_value(origin, direction, rng)
+    }
+
+    fn random(&self, origin: &Vec3, rng: &mut RngGen) -> Vec3 {
+        self.source.random(origin, rng)
+    }
+}
+
+fn outward_normal(hit: &HitRecord) -> Vec3 {
+    if hit.in_front_face() {
+        hit.normal().copy()
+    } else {
+        -hit.normal()
+    }
+}
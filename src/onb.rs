@@ -0,0 +1,43 @@
+use super::float::Float;
+use super::vec3::Vec3;
+
+// Orthonormal basis
+pub struct ONB {
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl ONB {
+    pub fn build_from_w(n: &Vec3) -> Self {
+        let w = n.unit_vector();
+        let a = if w.x().abs() > 0.9 { Vec3::up() } else { Vec3::right() };
+        let v = Vec3::cross(&w, &a).unit_vector();
+        let u = Vec3::cross(&w, &v);
+        Self { u, v, w }
+    }
+
+    pub fn u(&self) -> &Vec3 {
+        &self.u
+    }
+
+    pub fn v(&self) -> &Vec3 {
+        &self.v
+    }
+
+    pub fn w(&self) -> &Vec3 {
+        &self.w
+    }
+
+    pub fn local(&self, a: Float, b: Float, c: Float) -> Vec3 {
+        a*&self.u + b*&self.v + c*&self.w
+    }
+
+    pub fn local_vec(&self, a: &Vec3) -> Vec3 {
+        self.local(a.x(), a.y(), a.z())
+    }
+
+    pub fn to_local(&self, a: &Vec3) -> Vec3 {
+        Vec3::new(Vec3::dot(a, &self.u), Vec3::dot(a, &self.v), Vec3::dot(a, &self.w))
+    }
+}
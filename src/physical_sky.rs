@@ -0,0 +1,199 @@
+use super::float::{Float, PI};
+use super::vec3::Vec3;
+use super::color::{Color, PHOTOMETRIC_SCALE};
+use super::rng_float::RngGen;
+use super::onb::ONB;
+
+// Preetham, Shirley and Smits 1999 "A Practical Analytic Model for Daylight".
+// Everything below the horizon is ground lit by the sky and the sun.
+pub struct PhysicalSky {
+    sun_direction: Vec3,
+    sun_radiance: Color,
+    sun_cos_max: Float,
+    zenith: [Float; 3], // Y, x, y
+    perez: [[Float; 5]; 3],
+    perez_zenith: [Float; 3], // Perez function at the zenith, to normalize
+    ground: Color,
+    intensity: Float,
+}
+
+// NOTE(srp): The model works in kcd/m^2, this brings a white surface under
+// a midday sun to about one.
+const RADIANCE_SCALE: Float = 1000.0 * PHOTOMETRIC_SCALE;
+const SUN_ANGULAR_RADIUS: Float = 0.00465; // Radians
+const SUN_LUMINANCE: Float = 1.6e6; // kcd/m^2 before going through the air
+const SUN_SAMPLE_PROBABILITY: Float = 0.5;
+
+impl PhysicalSky {
+    // Angles in degrees, azimuth goes from -z towards +x. Turbidity goes
+    // from about 2 (clear) to 10 (hazy).
+    pub fn new(elevation: Float, azimuth: Float, turbidity: Float,
+               ground_albedo: Color, intensity: Float) -> Self {
+        let elevation = elevation.clamp(-90.0, 90.0).to_radians();
+        let azimuth = azimuth.to_radians();
+        let sun_direction = Vec3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos());
+        let t = turbidity.clamp(1.7, 10.0);
+
+        // The fit is only valid with the sun above the horizon
+        let theta_s = (0.5*PI - elevation).clamp(0.0, 0.5*PI);
+        let chi = (4.0/9.0 - t/120.0) * (PI - 2.0*theta_s);
+        let zenith_luminance = ((4.0453*t - 4.9710)*chi.tan() - 0.2155*t + 2.4192).max(0.0);
+        let zenith_chromaticity = |m: [[Float; 4]; 3]| {
+            let th = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.0];
+            let tt = [t*t, t, 1.0];
+            (0..3).map(|i| tt[i] * (0..4).map(|j| m[i][j]*th[j]).sum::<Float>()).sum::<Float>()
+        };
+        let zenith = [
+            zenith_luminance,
+            zenith_chromaticity([
+                [0.00166, -0.00375, 0.00209, 0.0],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ]),
+            zenith_chromaticity([
+                [0.00275, -0.00610, 0.00317, 0.0],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ]),
+        ];
+        let perez = [
+            [0.1787*t - 1.4630, -0.3554*t + 0.4275, -0.0227*t + 5.3251, 0.1206*t - 2.5771, -0.0670*t + 0.3703],
+            [-0.0193*t - 0.2592, -0.0665*t + 0.0008, -0.0004*t + 0.2125, -0.0641*t - 0.8989, -0.0033*t + 0.0452],
+            [-0.0167*t - 0.2608, -0.0950*t + 0.0092, -0.0079*t + 0.2102, -0.0441*t - 1.6537, -0.0109*t + 0.0529],
+        ];
+        let perez_zenith = [
+            perez_function(&perez[0], 1.0, theta_s),
+            perez_function(&perez[1], 1.0, theta_s),
+            perez_function(&perez[2], 1.0, theta_s),
+        ];
+
+        let sun_cos_max = SUN_ANGULAR_RADIUS.cos();
+        let sun_radiance = if elevation > 0.0 {
+            sun_transmittance(theta_s, t) * SUN_LUMINANCE
+        } else {
+            Color::zero()
+        };
+
+        let mut sky = Self {
+            sun_direction,
+            sun_radiance,
+            sun_cos_max,
+            zenith,
+            perez,
+            perez_zenith,
+            ground: Color::zero(),
+            intensity,
+        };
+
+        // Light reaching the ground, the sky part is integrated numerically
+        const STEPS: usize = 32;
+        let mut sky_irradiance = Color::zero();
+        for i in 0..STEPS {
+            let cos_theta = (i as Float + 0.5) / STEPS as Float;
+            let sin_theta = (1.0 - cos_theta*cos_theta).sqrt();
+            for j in 0..4*STEPS {
+                let phi = 2.0*PI * (j as Float + 0.5) / (4*STEPS) as Float;
+                let d = Vec3::new(sin_theta*phi.cos(), cos_theta, sin_theta*phi.sin());
+                sky_irradiance += cos_theta * sky.sky_radiance(&d);
+            }
+        }
+        // Each cell covers d(cos) dphi = (1/STEPS) * (2pi/(4 STEPS))
+        sky_irradiance *= 2.0*PI / (4*STEPS*STEPS) as Float;
+        let sun_solid_angle = 2.0*PI*(1.0 - sun_cos_max);
+        let sun_irradiance = (sun_solid_angle * sky.sun_direction.y().max(0.0)) * &sky.sun_radiance;
+        sky.ground = (ground_albedo * (sky_irradiance + sun_irradiance)) / PI;
+
+        sky
+    }
+
+    pub fn value(&self, direction: &Vec3) -> Color {
+        let d = direction.unit_vector();
+        let radiance = if d.y() < 0.0 {
+            self.ground.copy()
+        } else if Vec3::dot(&d, &self.sun_direction) >= self.sun_cos_max {
+            self.sky_radiance(&d) + &self.sun_radiance
+        } else {
+            self.sky_radiance(&d)
+        };
+        (self.intensity * RADIANCE_SCALE) * radiance
+    }
+
+    // Solid angle density of `random` picking `direction`
+    pub fn pdf_value(&self, direction: &Vec3) -> Float {
+        let uniform = 0.25 / PI;
+        if !self.sun_visible() {
+            return uniform
+        }
+        let d = direction.unit_vector();
+        let sun = if Vec3::dot(&d, &self.sun_direction) >= self.sun_cos_max {
+            (2.0*PI*(1.0 - self.sun_cos_max)).recip()
+        } else {
+            0.0
+        };
+        SUN_SAMPLE_PROBABILITY*sun + (1.0 - SUN_SAMPLE_PROBABILITY)*uniform
+    }
+
+    // Towards the sun disk half of the time, anywhere otherwise
+    pub fn random(&self, rng: &mut RngGen) -> Vec3 {
+        if self.sun_visible() && rng.get() < SUN_SAMPLE_PROBABILITY {
+            let frame = ONB::build_from_w(&self.sun_direction);
+            frame.local_vec(&Vec3::random_cone_direction(rng, 1.0 - self.sun_cos_max))
+        } else {
+            Vec3::random_unit(rng)
+        }
+    }
+
+    fn sun_visible(&self) -> bool {
+        self.sun_direction.y() > 0.0
+    }
+
+    // Sky without the sun disk, `d` is a unit vector above the horizon
+    fn sky_radiance(&self, d: &Vec3) -> Color {
+        let cos_theta = d.y().max(0.001);
+        let gamma = Vec3::dot(d, &self.sun_direction).clamp(-1.0, 1.0).acos();
+        let luminance = self.zenith[0] * perez_function(&self.perez[0], cos_theta, gamma)
+            / self.perez_zenith[0];
+        let x = self.zenith[1] * perez_function(&self.perez[1], cos_theta, gamma)
+            / self.perez_zenith[1];
+        let y = self.zenith[2] * perez_function(&self.perez[2], cos_theta, gamma)
+            / self.perez_zenith[2];
+        xyy_to_rgb(x, y, luminance)
+    }
+}
+
+// Perez et al. 1993 sky luminance distribution
+fn perez_function(c: &[Float; 5], cos_theta: Float, gamma: Float) -> Float {
+    let cos_gamma = gamma.cos();
+    (1.0 + c[0]*(c[1]/cos_theta).exp()) * (1.0 + c[2]*(c[3]*gamma).exp() + c[4]*cos_gamma*cos_gamma)
+}
+
+// Rayleigh and aerosol extinction of sunlight (Preetham et al. appendix)
+// sampled at red, green and blue wavelengths
+fn sun_transmittance(theta_s: Float, turbidity: Float) -> Color {
+    let theta_degrees = theta_s.to_degrees();
+    let relative_mass = (theta_s.cos() + 0.15*(93.885 - theta_degrees).powf(-1.253)).recip();
+    let beta = 0.04608*turbidity - 0.04586;
+    let transmittance = |lambda_um: Float| {
+        let rayleigh = (-relative_mass * 0.008735 * lambda_um.powf(-4.08)).exp();
+        let aerosol = (-relative_mass * beta * lambda_um.powf(-1.3)).exp();
+        rayleigh * aerosol
+    };
+    Color::new(transmittance(0.65), transmittance(0.55), transmittance(0.45))
+}
+
+// CIE xyY to linear sRGB
+fn xyy_to_rgb(x: Float, y: Float, luminance: Float) -> Color {
+    if y <= 0.0 {
+        return Color::zero()
+    }
+    let cx = x * luminance / y;
+    let cy = luminance;
+    let cz = (1.0 - x - y) * luminance / y;
+    Color::new(
+        (3.2406*cx - 1.5372*cy - 0.4986*cz).max(0.0),
+        (-0.9689*cx + 1.8758*cy + 0.0415*cz).max(0.0),
+        (0.0557*cx - 0.2040*cy + 1.0570*cz).max(0.0))
+}
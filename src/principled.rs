@@ -0,0 +1,367 @@
+use super::float::{Float, FRAC_1_PI};
+use super::vec3::Vec3;
+use super::rng_float::RngGen;
+use super::material::{Material, Scatter};
+use super::ray::Ray;
+use super::hittable::HitRecord;
+use super::color::Color;
+use super::texture::{Texture, SolidColor};
+use super::onb::ONB;
+use super::microfacet::GGX;
+use super::fresnel::{schlick, schlick_color};
+
+use std::rc::Rc;
+
+// Disney style uber-material. Every parameter is a texture, scalar
+// parameters read the first channel of theirs.
+//
+// The BSDF is a stack of layers, each one taking the energy left by the one
+// above it: clearcoat, then either metal or a dielectric specular layer over
+// a transmissive or diffuse (plus sheen) base. A single lobe is picked per
+// scatter with the probability of its energy, so weights never exceed one.
+pub struct Principled {
+    base_color: Rc<dyn Texture>,
+    metallic: Rc<dyn Texture>,
+    roughness: Rc<dyn Texture>,
+    specular: Rc<dyn Texture>,
+    specular_tint: Rc<dyn Texture>,
+    sheen: Rc<dyn Texture>,
+    clearcoat: Rc<dyn Texture>,
+    transmission: Rc<dyn Texture>,
+    emission: Rc<dyn Texture>,
+    emissive: bool,
+}
+
+pub struct PrincipledBuilder {
+    base_color: Rc<dyn Texture>,
+    metallic: Rc<dyn Texture>,
+    roughness: Rc<dyn Texture>,
+    specular: Rc<dyn Texture>,
+    specular_tint: Rc<dyn Texture>,
+    sheen: Rc<dyn Texture>,
+    clearcoat: Rc<dyn Texture>,
+    transmission: Rc<dyn Texture>,
+    emission: Rc<dyn Texture>,
+    emissive: bool,
+}
+
+impl PrincipledBuilder {
+    pub fn new() -> Self {
+        let scalar = |value: Float| -> Rc<dyn Texture> {
+            Rc::new(SolidColor::new_rgb(value, value, value))
+        };
+
+        PrincipledBuilder {
+            base_color: scalar(0.8),
+            metallic: scalar(0.0),
+            roughness: scalar(0.5),
+            specular: scalar(0.5),
+            specular_tint: scalar(0.0),
+            sheen: scalar(0.0),
+            clearcoat: scalar(0.0),
+            transmission: scalar(0.0),
+            emission: scalar(0.0),
+            emissive: false,
+        }
+    }
+
+    pub fn base_color(&mut self, base_color: Rc<dyn Texture>) -> &mut Self {
+        self.base_color = base_color;
+        self
+    }
+
+    pub fn metallic(&mut self, metallic: Rc<dyn Texture>) -> &mut Self {
+        self.metallic = metallic;
+        self
+    }
+
+    pub fn roughness(&mut self, roughness: Rc<dyn Texture>) -> &mut Self {
+        self.roughness = roughness;
+        self
+    }
+
+    // Reflectance at normal incidence of the dielectric layer, 0.5 is 4%
+    pub fn specular(&mut self, specular: Rc<dyn Texture>) -> &mut Self {
+        self.specular = specular;
+        self
+    }
+
+    pub fn specular_tint(&mut self, specular_tint: Rc<dyn Texture>) -> &mut Self {
+        self.specular_tint = specular_tint;
+        self
+    }
+
+    pub fn sheen(&mut self, sheen: Rc<dyn Texture>) -> &mut Self {
+        self.sheen = sheen;
+        self
+    }
+
+    pub fn clearcoat(&mut self, clearcoat: Rc<dyn Texture>) -> &mut Self {
+        self.clearcoat = clearcoat;
+        self
+    }
+
+    pub fn transmission(&mut self, transmission: Rc<dyn Texture>) -> &mut Self {
+        self.transmission = transmission;
+        self
+    }
+
+    pub fn emission(&mut self, emission: Rc<dyn Texture>) -> &mut Self {
+        self.emission = emission;
+        self.emissive = true;
+        self
+    }
+
+    pub fn build(&self) -> Principled {
+        Principled::new(self)
+    }
+}
+
+impl Default for PrincipledBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Parameters evaluated at a hit
+struct Params {
+    base_color: Color,
+    metallic: Float,
+    roughness: Float,
+    specular: Float,
+    specular_tint: Float,
+    sheen: Float,
+    clearcoat: Float,
+    transmission: Float,
+}
+
+// Probability of picking each lobe, they add up to one
+struct Lobes {
+    clearcoat: Float,
+    metal: Float,
+    specular: Float,
+    transmission: Float,
+    diffuse: Float,
+}
+
+impl Principled {
+    const CLEARCOAT_ROUGHNESS: Float = 0.1;
+    const CLEARCOAT_F0: Float = 0.04;
+
+    fn new(data: &PrincipledBuilder) -> Self {
+        Principled {
+            base_color: data.base_color.clone(),
+            metallic: data.metallic.clone(),
+            roughness: data.roughness.clone(),
+            specular: data.specular.clone(),
+            specular_tint: data.specular_tint.clone(),
+            sheen: data.sheen.clone(),
+            clearcoat: data.clearcoat.clone(),
+            transmission: data.transmission.clone(),
+            emission: data.emission.clone(),
+            emissive: data.emissive,
+        }
+    }
+
+    fn params(&self, hit: &HitRecord) -> Params {
+        let (u, v, p) = (hit.u(), hit.v(), hit.p());
+        let scalar = |tex: &Rc<dyn Texture>| tex.value(u, v, p).x().clamp(0.0, 1.0);
+        Params {
+            base_color: self.base_color.value(u, v, p),
+            metallic: scalar(&self.metallic),
+            roughness: scalar(&self.roughness),
+            specular: scalar(&self.specular),
+            specular_tint: scalar(&self.specular_tint),
+            sheen: scalar(&self.sheen),
+            clearcoat: scalar(&self.clearcoat),
+            transmission: scalar(&self.transmission),
+        }
+    }
+
+    fn lobes(params: &Params, cos_o: Float) -> Lobes {
+        let clearcoat = params.clearcoat * schlick(cos_o, Self::CLEARCOAT_F0);
+        let base = 1.0 - clearcoat;
+        let metal = base * params.metallic;
+        let dielectric = base - metal;
+        let specular = dielectric * schlick(cos_o, Self::dielectric_f0(params));
+        let transmission = (dielectric - specular) * params.transmission;
+        let diffuse = dielectric - specular - transmission;
+        Lobes { clearcoat, metal, specular, transmission, diffuse }
+    }
+
+    fn dielectric_f0(params: &Params) -> Float {
+        0.08 * params.specular
+    }
+
+    // Index of refraction matching the reflectance of the specular layer
+    fn refraction_ratio(params: &Params, hit: &HitRecord) -> Float {
+        let sqrt_f0 = Self::dielectric_f0(params).sqrt().min(0.99);
+        let ior = (1.0 + sqrt_f0) / (1.0 - sqrt_f0);
+        if hit.in_front_face() { ior.recip() } else { ior }
+    }
+
+    // Hue of the base color, normalized so no channel goes over one
+    fn tint(params: &Params) -> Color {
+        let c = &params.base_color;
+        let max = c.x().max(c.y()).max(c.z());
+        if max > 0.0 { c / max } else { Color::one() }
+    }
+
+    fn specular_color(params: &Params) -> Color {
+        Color::lerp(&Color::one(), &Self::tint(params), params.specular_tint)
+    }
+
+    // Base color fading into the sheen color at grazing angles
+    fn diffuse_color(params: &Params, wo: &Vec3, wi: &Vec3) -> Color {
+        let h = (wo + wi).unit_vector();
+        let sheen_amount = params.sheen * (1.0 - Vec3::dot(wi, &h)).powi(5);
+        Color::lerp(&params.base_color, &Self::tint(params), sheen_amount)
+    }
+
+    fn sample_reflection(distribution: &GGX, wo: &Vec3, rng: &mut RngGen)
+            -> Option<(Vec3, Vec3, Float)> {
+        let m = distribution.sample_visible_normal(wo, rng.get(), rng.get());
+        let wi = (-wo).reflect(&m);
+        if wi.z() <= 0.0 {
+            return None
+        }
+        let weight = distribution.g2(wo, &wi) / distribution.g1(wo);
+        Some((wi, m, weight))
+    }
+
+    // BSDF times cosine and sampling density of every non specular lobe
+    fn eval_pdf(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> (Color, Float) {
+        let params = self.params(hit);
+        let frame = ONB::build_from_w(hit.normal());
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let wi = frame.to_local(scattered);
+        if wo.z() <= 0.0 {
+            return (Color::zero(), 0.0)
+        }
+
+        let lobes = Self::lobes(&params, wo.z());
+        let distribution = GGX::new(params.roughness);
+        let coat = GGX::new(Self::CLEARCOAT_ROUGHNESS);
+        let mut f_cos = Color::zero();
+        let mut pdf = 0.0;
+
+        if let Some((_, f, p)) = coat.reflection(&wo, &wi) {
+            f_cos += (lobes.clearcoat * f) * Color::one();
+            pdf += lobes.clearcoat * p;
+        }
+        if !distribution.is_smooth() {
+            if let Some((m, f, p)) = distribution.reflection(&wo, &wi) {
+                let fresnel = schlick_color(Vec3::dot(&wo, &m), &params.base_color);
+                f_cos += (lobes.metal * f) * fresnel;
+                f_cos += (lobes.specular * f) * Self::specular_color(&params);
+                pdf += (lobes.metal + lobes.specular) * p;
+            }
+            let refraction_ratio = Self::refraction_ratio(&params, hit);
+            if let Some((_, f, p)) = distribution.transmission(&wo, &wi, refraction_ratio) {
+                f_cos += (lobes.transmission * f) * &params.base_color;
+                pdf += lobes.transmission * p;
+            }
+        }
+        if wi.z() > 0.0 {
+            let cosine = wi.z() * FRAC_1_PI;
+            f_cos += (lobes.diffuse * cosine) * Self::diffuse_color(&params, &wo, &wi);
+            pdf += lobes.diffuse * cosine;
+        }
+
+        (f_cos, pdf)
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: &Ray, hit: &HitRecord, rng: &mut RngGen) -> Scatter {
+        let params = self.params(hit);
+        let frame = ONB::build_from_w(hit.normal());
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        if wo.z() <= 0.0 {
+            return Scatter::None
+        }
+
+        let lobes = Self::lobes(&params, wo.z());
+        let distribution = GGX::new(params.roughness);
+        let pick = rng.get();
+        let metal_end = lobes.clearcoat + lobes.metal;
+        let specular_end = metal_end + lobes.specular;
+        let transmission_end = specular_end + lobes.transmission;
+
+        let mut specular = distribution.is_smooth();
+        let sample: Option<(Vec3, Color)> = if pick < lobes.clearcoat {
+            specular = false;
+            let coat = GGX::new(Self::CLEARCOAT_ROUGHNESS);
+            Self::sample_reflection(&coat, &wo, rng)
+                .map(|(wi, _, weight)| (wi, weight * Color::one()))
+        } else if pick < metal_end {
+            Self::sample_reflection(&distribution, &wo, rng).map(|(wi, m, weight)| {
+                let fresnel = schlick_color(Vec3::dot(&wo, &m), &params.base_color);
+                (wi, weight * fresnel)
+            })
+        } else if pick < specular_end {
+            let tint = Self::specular_color(&params);
+            Self::sample_reflection(&distribution, &wo, rng)
+                .map(|(wi, _, weight)| (wi, weight * tint))
+        } else if pick < transmission_end {
+            let refraction_ratio = Self::refraction_ratio(&params, hit);
+            let m = distribution.sample_visible_normal(&wo, rng.get(), rng.get());
+            let cos_theta = Vec3::dot(&wo, &m);
+            let sin2_theta = 1.0 - cos_theta*cos_theta;
+            let total_internal_reflection = refraction_ratio*refraction_ratio*sin2_theta > 1.0;
+            let wi = if total_internal_reflection {
+                (-&wo).reflect(&m)
+            } else {
+                (-&wo).refract(&m, refraction_ratio)
+            };
+            // Rough or bumped surfaces can send the sample out the wrong side,
+            // like the rough dielectric those are lost
+            let leaves_right_side = if total_internal_reflection { wi.z() > 0.0 } else { wi.z() < 0.0 };
+            if leaves_right_side {
+                let weight = distribution.g2(&wo, &wi) / distribution.g1(&wo);
+                Some((wi, weight * &params.base_color))
+            } else {
+                None
+            }
+        } else {
+            specular = false;
+            let wi = Vec3::random_cosine_direction(rng);
+            let attenuation = Self::diffuse_color(&params, &wo, &wi);
+            Some((wi, attenuation))
+        };
+
+        match sample {
+            Some((wi, attenuation)) => {
+                let new_ray = Ray::new(hit.p(), &frame.local_vec(&wi), r_in.time());
+                if specular {
+                    Scatter::Specular(new_ray, attenuation)
+                } else {
+                    let pdf = self.pdf(r_in, hit, &new_ray.direction().unit_vector());
+                    Scatter::Some(new_ray, attenuation, pdf)
+                }
+            },
+            None => Scatter::None
+        }
+    }
+
+    fn eval(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Color {
+        self.eval_pdf(r_in, hit, scattered).0
+    }
+
+    fn pdf(&self, r_in: &Ray, hit: &HitRecord, scattered: &Vec3) -> Float {
+        self.eval_pdf(r_in, hit, scattered).1
+    }
+
+    fn emmited(&self, _r_in: &Ray, hit: &HitRecord) -> Color {
+        self.emission.value(hit.u(), hit.v(), hit.p())
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.emissive
+    }
+
+    // The diffuse and clearcoat lobes are never specular
+    fn has_non_specular(&self) -> bool {
+        true
+    }
+}
@@ -40,4 +40,16 @@ impl Hittable for RectPrism {
     fn hit(&self, r:&Ray, t_min:Float, t_max:Float, rng: &mut RngGen) -> Option<HitRecord> {
         self.sides.hit(r, t_min, t_max, rng)
     }
+
+    fn is_emissive(&self) -> bool {
+        self.sides.objects().iter().any(|side| side.is_emissive())
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        self.sides.pdf_value(origin, direction, rng)
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut RngGen) -> Vec3 {
+        self.sides.random(origin, rng)
+    }
 }
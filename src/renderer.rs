@@ -3,19 +3,28 @@ use super::rng_float::RngGen;
 use super::color::Color;
 use super::ray::Ray;
 use super::pixel_buffer::PixelBuffer;
-use super::hittable::Hittable;
+use super::hittable::{Hittable, HitRecord};
 use super::hittable_list::HittableList;
-use super::camera::{CameraBuilder, Camera};
+use super::camera::{CameraBuilder, Camera, StereoLayout};
 use super::material::Scatter;
+use super::vec3::Vec3;
+type Point3 = Vec3;
+use super::environment_map::EnvironmentMap;
+use super::physical_sky::PhysicalSky;
+use super::delta_light::DeltaLight;
 
 use std::sync::Arc;
 use std::rc::Rc;
 use std::sync::RwLock;
 
 pub struct RenderInfo {
-    camera: Camera,
+    cameras: Vec<Camera>, // Left and right eye for stereo
+    stereo_layout: Option<StereoLayout>,
     sky: Sky,
     world: Rc::<HittableList>,
+    lights: HittableList,
+    delta_lights: Vec<Rc<dyn DeltaLight>>,
+    light_scale: Float,
     pixel_buffer_rwlock: Arc<RwLock<PixelBuffer>>, 
     image_width: usize,
     image_height: usize,
@@ -27,8 +36,49 @@ pub struct RenderInfo {
 pub enum Sky {
     Gradient(Color, Color),
     SolidColor(Color),
+    EnvironmentMap(EnvironmentMap),
+    Physical(PhysicalSky),
 }
 
+impl Sky {
+    pub fn color(&self, r: &Ray) -> Color {
+        match self {
+            Sky::Gradient(c1, c2) => {
+                let unit_direction = r.direction().unit_vector();
+                let t = 0.5 * (unit_direction.y() + 1.0);
+                Color::lerp(c1, c2, t)
+            },
+            Sky::SolidColor(c) => c.copy(),
+            Sky::EnvironmentMap(map) => map.value(r.direction()),
+            Sky::Physical(sky) => sky.value(r.direction())
+        }
+    }
+
+    // Whether the renderer samples the sky as a light
+    pub fn is_sampled(&self) -> bool {
+        matches!(self, Sky::EnvironmentMap(_) | Sky::Physical(_))
+    }
+
+    pub fn pdf_value(&self, direction: &Vec3) -> Float {
+        match self {
+            Sky::EnvironmentMap(map) => map.pdf_value(direction),
+            Sky::Physical(sky) => sky.pdf_value(direction),
+            _ => 0.0
+        }
+    }
+
+    pub fn random(&self, rng: &mut RngGen) -> Vec3 {
+        match self {
+            Sky::EnvironmentMap(map) => map.random(rng),
+            Sky::Physical(sky) => sky.random(rng),
+            _ => Vec3::random_unit(rng)
+        }
+    }
+}
+
+// Bounces before Russian roulette can end a path
+const MIN_BOUNCES: i32 = 3;
+
 impl RenderInfo {
     pub fn new(pixel_buffer_rwlock: Arc<RwLock<PixelBuffer>>,
                cam_settings: &mut CameraBuilder) -> Self {
@@ -40,23 +90,97 @@ impl RenderInfo {
         Self {
             sky: Sky::Gradient(Color::one(), Color::new(0.5, 0.7, 1.0)),
             world: Rc::new(HittableList::new()),
+            lights: HittableList::new(),
+            delta_lights: Vec::new(),
+            light_scale: 1.0,
             pixel_buffer_rwlock,
             image_width,
             image_height,
-            camera: cam_settings.aspect_ratio(aspect_ratio).build()
+            cameras: cam_settings.aspect_ratio(aspect_ratio).build_views(),
+            stereo_layout: cam_settings.stereo_layout()
         }
     }
 
+    // Replaces the camera, for rendering the next frame of an animation
+    pub fn camera(&mut self, cam_settings: &mut CameraBuilder) -> &mut Self {
+        let aspect_ratio = self.pixel_buffer_rwlock.read().unwrap().aspect_ratio();
+        self.cameras = cam_settings.aspect_ratio(aspect_ratio).build_views();
+        self.stereo_layout = cam_settings.stereo_layout();
+        self
+    }
+
     pub fn sky(&mut self, sky: Sky) -> &mut Self {
         self.sky = sky;
         self
     }
 
     pub fn world(&mut self, world: Rc<HittableList>) -> &mut Self {
+        self.lights.clear();
+        world.collect_lights(&mut self.lights);
         self.world = world;
         self
     }
 
+    pub fn delta_lights(&mut self, delta_lights: Vec<Rc<dyn DeltaLight>>) -> &mut Self {
+        self.delta_lights = delta_lights;
+        self
+    }
+
+    // Multiplies the emission of every light in the scene, the sky is left as is
+    pub fn light_scale(&mut self, light_scale: Float) -> &mut Self {
+        self.light_scale = light_scale;
+        self
+    }
+
+    // Chance of sampling the sky instead of the light list
+    fn sky_sample_probability(&self) -> Float {
+        if !self.sky.is_sampled() {
+            0.0
+        } else if self.lights.objects().is_empty() {
+            1.0
+        } else {
+            0.5
+        }
+    }
+
+    // Direction from `origin` towards the sky or one of the lights, None when
+    // there is nothing to sample
+    fn random_light_direction(&self, origin: &Point3, rng: &mut RngGen) -> Option<Vec3> {
+        let sky_probability = self.sky_sample_probability();
+        if sky_probability > 0.0 && rng.get() < sky_probability {
+            Some(self.sky.random(rng))
+        } else if !self.lights.objects().is_empty() {
+            Some(self.lights.random(origin, rng))
+        } else {
+            None
+        }
+    }
+
+    // Solid angle density of `random_light_direction` picking `direction`
+    fn light_pdf(&self, origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        let sky_probability = self.sky_sample_probability();
+        let mut pdf = 0.0;
+        if sky_probability > 0.0 {
+            pdf += sky_probability * self.sky.pdf_value(direction);
+        }
+        if sky_probability < 1.0 && !self.lights.objects().is_empty() {
+            pdf += (1.0 - sky_probability) * self.lights.pdf_value(origin, direction, rng);
+        }
+        pdf
+    }
+
+    // Camera that sees the point (u, v) of the image and where it lands in
+    // that camera's view
+    fn camera_at(&self, u: Float, v: Float) -> (&Camera, Float, Float) {
+        match self.stereo_layout {
+            Some(layout) => {
+                let (eye, u, v) = layout.eye_uv(u, v);
+                (&self.cameras[eye], u, v)
+            },
+            None => (&self.cameras[0], u, v)
+        }
+    }
+
     pub fn render(&mut self, samples_per_pixel: i32, max_bounces: i32, rng: &mut RngGen) {
         for j in (0..self.image_height).rev() {
             eprint!("\nScanlines remaining: {}\n", j+1);
@@ -65,8 +189,10 @@ impl RenderInfo {
                 for _ in 0..samples_per_pixel+1 {
                     let u = ((i as Float) + rng.get()) / ((self.image_width-1) as Float);
                     let v = ((j as Float) + rng.get()) / ((self.image_height-1) as Float);
-                    let r: Ray = self.camera.get_ray(u, v, rng);
-                    pixel_color += ray_color(&r, self, max_bounces+1, rng);
+                    let (camera, u, v) = self.camera_at(u, v);
+                    if let Some((r, weight)) = camera.get_ray(u, v, rng) {
+                        pixel_color += (weight * camera.exposure()) * ray_color(r, self, max_bounces, rng);
+                    }
                 }
 
                 let mut buffer = loop {
@@ -81,25 +207,140 @@ impl RenderInfo {
     }
 }
 
-fn ray_color(this: &Ray, info: &RenderInfo, bounces_left: i32, rng: &mut RngGen)
-        -> Color {
-    if bounces_left <= 0 { return Color::zero() }
+// NOTE(srp): `bsdf_pdf` is the pdf with which the material that scattered
+// the current ray picked it. Lights hit after such a bounce were also sampled
+// directly, so both estimates get weighted with the power heuristic. It is
+// None for camera rays and specular bounces, which count emission fully.
+fn ray_color(camera_ray: Ray, info: &RenderInfo, max_bounces: i32, rng: &mut RngGen) -> Color {
+    let mut radiance = Color::zero();
+    let mut throughput = Color::one();
+    let mut ray = camera_ray;
+    let mut bsdf_pdf: Option<Float> = None;
 
-    if let Some(hit) = info.world.hit(this, 0.001, Float::INFINITY, rng) {
-        let emmited = hit.mat().emmited(hit.u(), hit.v(), hit.p());
-        return if let Scatter::Some(scatter_ray, scatter_color) = hit.mat().scatter(this, &hit, rng) {
-            emmited + scatter_color*ray_color(&scatter_ray, info, bounces_left-1, rng)
-        } else {
-            emmited
+    for bounce in 0..=max_bounces {
+        let hit = match info.world.hit(&ray, 0.001, Float::INFINITY, rng) {
+            Some(hit) => hit,
+            None => {
+                let mut sky = info.sky.color(&ray);
+                if let Some(bsdf_pdf) = bsdf_pdf {
+                    if info.sky.is_sampled() {
+                        let light_pdf = info.light_pdf(ray.origin(), ray.direction(), rng);
+                        sky *= power_heuristic(bsdf_pdf, light_pdf);
+                    }
+                }
+                radiance += &throughput * sky;
+                break
+            }
+        };
+
+        let mut emmited = info.light_scale * hit.mat().emmited(&ray, &hit);
+        if let Some(bsdf_pdf) = bsdf_pdf {
+            if is_sampled_light(&ray, &hit, info, rng) {
+                let light_pdf = info.light_pdf(ray.origin(), ray.direction(), rng);
+                emmited *= power_heuristic(bsdf_pdf, light_pdf);
+            }
+        }
+        radiance += &throughput * emmited;
+
+        // NOTE(srp): `eval` and `pdf` cover every lobe that isn't specular, so
+        // lights are sampled whichever lobe `scatter` picks. Otherwise
+        // materials mixing both kinds lose direct light and the MIS weights
+        // don't add up to one.
+        if hit.mat().has_non_specular() {
+            radiance += &throughput * sample_lights(&ray, &hit, info, rng);
+            radiance += &throughput * sample_delta_lights(&ray, &hit, info, rng);
+        }
+
+        match hit.mat().scatter(&ray, &hit, rng) {
+            Scatter::Some(scatter_ray, scatter_color, pdf) => {
+                throughput = throughput * scatter_color;
+                bsdf_pdf = Some(pdf);
+                ray = scatter_ray;
+            },
+            Scatter::Specular(scatter_ray, scatter_color) => {
+                throughput = throughput * scatter_color;
+                bsdf_pdf = None;
+                ray = scatter_ray;
+            },
+            Scatter::None => break
+        }
+
+        // Russian roulette, surviving paths make up for the terminated ones
+        if bounce >= MIN_BOUNCES {
+            let survive = throughput.x().max(throughput.y()).max(throughput.z()).min(0.95);
+            if rng.get() >= survive {
+                break
+            }
+            throughput /= survive;
         }
     }
 
-    match &info.sky {
-        Sky::Gradient(c1, c2) => {
-            let unit_direction = this.direction().unit_vector();
-            let t = 0.5 * (unit_direction.y() + 1.0);
-            Color::lerp(&c1, &c2, t)
+    radiance
+}
+
+// Light arriving at `hit` straight from the sky or a random point on a
+// random light
+fn sample_lights(this: &Ray, hit: &HitRecord, info: &RenderInfo, rng: &mut RngGen) -> Color {
+    let to_light = match info.random_light_direction(hit.p(), rng) {
+        Some(to_light) => to_light,
+        None => return Color::zero()
+    };
+    let pdf = info.light_pdf(hit.p(), &to_light, rng);
+    if pdf <= 0.0 {
+        return Color::zero()
+    }
+
+    let shadow_ray = Ray::new(hit.p(), &to_light, this.time());
+    let emmited = match info.world.hit(&shadow_ray, 0.001, Float::INFINITY, rng) {
+        Some(light_hit) if is_sampled_light(&shadow_ray, &light_hit, info, rng) => {
+            info.light_scale * light_hit.mat().emmited(&shadow_ray, &light_hit)
         },
-        Sky::SolidColor(c) => c.copy()
+        None if info.sky.is_sampled() => info.sky.color(&shadow_ray),
+        _ => return Color::zero()
+    };
+
+    let direction = to_light.unit_vector();
+    let f_cos = hit.mat().eval(this, hit, &direction);
+    let bsdf_pdf = hit.mat().pdf(this, hit, &direction);
+    (power_heuristic(pdf, bsdf_pdf) / pdf) * (emmited * f_cos)
+}
+
+// Light arriving at `hit` from every delta light, they can't be hit by
+// chance so there is nothing to weight against
+fn sample_delta_lights(this: &Ray, hit: &HitRecord, info: &RenderInfo, rng: &mut RngGen) -> Color {
+    let mut direct = Color::zero();
+    for light in info.delta_lights.iter() {
+        let (direction, distance, light_radiance) = match light.sample(hit.p()) {
+            Some(sample) => sample,
+            None => continue
+        };
+        let shadow_ray = Ray::new(hit.p(), &direction, this.time());
+        if info.world.hit(&shadow_ray, 0.001, distance - 0.001, rng).is_some() {
+            continue
+        }
+        direct += light_radiance * hit.mat().eval(this, hit, &direction);
+    }
+    info.light_scale * direct
+}
+
+// Weight for a sample taken with density `pdf` when `other_pdf` could have
+// taken it too
+fn power_heuristic(pdf: Float, other_pdf: Float) -> Float {
+    let pdf2 = pdf*pdf;
+    let other_pdf2 = other_pdf*other_pdf;
+    if pdf2 + other_pdf2 <= 0.0 { 0.0 } else { pdf2 / (pdf2 + other_pdf2) }
+}
+
+// Whether `hit` landed on one of the objects in the light list
+fn is_sampled_light(this: &Ray, hit: &HitRecord, info: &RenderInfo, rng: &mut RngGen) -> bool {
+    if !hit.mat().is_emissive() {
+        return false
+    }
+    // NOTE(srp): The light list doesn't know which object was hit, so it
+    // checks whether it finds the same intersection.
+    const EPS: Float = 0.0001;
+    match info.lights.hit(this, hit.t() - EPS, hit.t() + EPS, rng) {
+        Some(light_hit) => (light_hit.t() - hit.t()).abs() < EPS,
+        None => false
     }
 }
@@ -4,12 +4,14 @@ use super::vec3::Vec3;
 type Point3 = Vec3;
 use super::ray::Ray;
 use super::hittable::{HitRecord, Hittable};
+use super::hittable_list::HittableList;
 use super::aabb::AABB;
 
 use std::rc::Rc;
 
 pub struct RotateY {
     source: Rc<dyn Hittable>,
+    angle: Float,
     sin_theta: Float,
     cos_theta: Float,
     box_opt: Option<AABB>
@@ -48,7 +50,23 @@ impl RotateY {
             None
         };
 
-        Self { source, sin_theta, cos_theta, box_opt }
+        Self { source, angle, sin_theta, cos_theta, box_opt }
+    }
+
+    // World space to object space
+    fn to_object(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta*v.x() - self.sin_theta*v.z(),
+            v.y(),
+            self.sin_theta*v.x() + self.cos_theta*v.z())
+    }
+
+    // Object space to world space
+    fn to_world(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta*v.x() + self.sin_theta*v.z(),
+            v.y(),
+            -self.sin_theta*v.x() + self.cos_theta*v.z())
     }
 }
 
@@ -79,11 +97,35 @@ impl Hittable for RotateY {
                 let normal_x = self.cos_theta*hit.normal().x() + self.sin_theta*hit.normal().z();
                 let normal_z = -self.sin_theta*hit.normal().x() + self.cos_theta*hit.normal().z();
                 let normal = Vec3::new(normal_x, hit.normal().y(), normal_z);
+                let dpdu = self.to_world(hit.dpdu());
+                let dpdv = self.to_world(hit.dpdv());
                 hit.set_p(p);
                 hit.set_face_normal(&rotated_r, normal);
+                hit.set_tangents(dpdu, dpdv);
                 Some(hit)
             },
             None => None
         }
     }
+
+    fn is_emissive(&self) -> bool {
+        self.source.is_emissive()
+    }
+
+    // Lights inside get rotated along with it
+    fn collect_lights(&self, lights: &mut HittableList) {
+        let mut inside = HittableList::new();
+        self.source.collect_lights(&mut inside);
+        for light in inside.objects() {
+            lights.add(Rc::new(RotateY::new(light.clone(), self.angle)));
+        }
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        self.source.pdf_value(&self.to_object(origin), &self.to_object(direction), rng)
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut RngGen) -> Vec3 {
+        self.to_world(&self.source.random(&self.to_object(origin), rng))
+    }
 }
@@ -6,6 +6,8 @@ type Point3 = Vec3;
 use super::hittable::*;
 use super::material::Material;
 use super::aabb::AABB;
+use super::ray::Ray;
+use super::onb::ONB;
 
 pub struct Sphere {
     center: Point3,
@@ -37,10 +39,26 @@ impl Sphere {
         *out_u = phi * FRAC_1_PI * 0.5;
         *out_v = theta * FRAC_1_PI;
     }
+
+    // 1 - cos_theta_max of the cone the sphere fills, seen from
+    // `distance_squared` away.
+    // NOTE(srp): Computed straight from cos_theta_max it rounds to 0 for
+    // small, far away spheres, this form of it doesn't.
+    fn one_minus_cos_max(&self, distance_squared: Float) -> Float {
+        let sin2_theta_max = self.radius*self.radius / distance_squared;
+        sin2_theta_max / (1.0 + (1.0 - sin2_theta_max).sqrt())
+    }
+
+    fn get_tangents(p: &Point3, radius: Float) -> (Vec3, Vec3) {
+        let sin_theta = (1.0 - p.y()*p.y()).max(1e-8).sqrt();
+        let dpdu = 2.0 * PI * radius * Vec3::new(p.z(), 0.0, -p.x());
+        let dpdv = PI * radius * Vec3::new(-p.x()*p.y()/sin_theta, sin_theta, -p.y()*p.z()/sin_theta);
+        (dpdu, dpdv)
+    }
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, r:&crate::ray::Ray, t_min:Float, t_max:Float, _rng: &mut RngGen) -> Option<HitRecord> {
+    fn hit(&self, r:&Ray, t_min:Float, t_max:Float, _rng: &mut RngGen) -> Option<HitRecord> {
         let ref oc: Vec3 = r.origin() - self.center();
         let a = r.direction().length_squared();
         let half_b = Vec3::dot(oc, r.direction());
@@ -68,7 +86,9 @@ impl Hittable for Sphere {
         let mut u: Float = 0.0;
         let mut v: Float = 0.0;
         Sphere::get_uv(&outward_normal, &mut u, &mut v);
-        let rec = HitRecord::new(&r, outward_normal, rec_mat, rec_t, u, v);
+        let (dpdu, dpdv) = Sphere::get_tangents(&outward_normal, self.radius());
+        let mut rec = HitRecord::new(&r, outward_normal, rec_mat, rec_t, u, v);
+        rec.set_tangents(dpdu, dpdv);
         Some(rec)
     }
 
@@ -76,4 +96,33 @@ impl Hittable for Sphere {
         let ref radius_vec = Vec3::new(self.radius, self.radius, self.radius);
         Some(AABB::new(self.center() - radius_vec, self.center() + radius_vec))
     }
+
+    fn is_emissive(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    // Uniform over the cone of directions that hit the sphere
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        let ray = Ray::new(origin, direction, 0.0);
+        if self.hit(&ray, 0.001, Float::INFINITY, rng).is_none() {
+            return 0.0
+        }
+        let distance_squared = (self.center() - origin).length_squared();
+        if distance_squared <= self.radius*self.radius {
+            // Every direction hits the sphere from the inside
+            return 0.25 * FRAC_1_PI
+        }
+        let solid_angle = 2.0*PI*self.one_minus_cos_max(distance_squared);
+        solid_angle.recip()
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut RngGen) -> Vec3 {
+        let direction = self.center() - origin;
+        let distance_squared = direction.length_squared();
+        if distance_squared <= self.radius*self.radius {
+            return Vec3::random_unit(rng)
+        }
+        let frame = ONB::build_from_w(&direction);
+        frame.local_vec(&Vec3::random_cone_direction(rng, self.one_minus_cos_max(distance_squared)))
+    }
 }
@@ -0,0 +1,105 @@
+use super::float::{Float, PI};
+use super::rng_float::RngGen;
+use super::vec3::Vec3;
+type Point3 = Vec3;
+use super::ray::Ray;
+use super::hittable::{HitRecord, Hittable};
+use super::hittable_list::HittableList;
+use super::aabb::AABB;
+
+use std::rc::Rc;
+
+// Keeps turning around the z axis as time goes by, counterclockwise when
+// seen from +z. Put it inside a Translate to spin around another point.
+// Like a BVH, it's only bounded for rays between `time0` and `time1`.
+pub struct SpinZ {
+    source: Rc<dyn Hittable>,
+    revolutions: Float, // Per unit of scene time
+    time0: Float,
+    time1: Float,
+    box_opt: Option<AABB>
+}
+
+impl SpinZ {
+    pub fn new(source: Rc<dyn Hittable>, revolutions: Float, time0: Float, time1: Float) -> Self {
+        // Any angle can show up during a frame, the box holds every one of them
+        let box_opt = source.bounding_box(time0, time1).map(|src_box| {
+            let reach = |a: Float, b: Float| a.abs().max(b.abs());
+            let x = reach(src_box.min().x(), src_box.max().x());
+            let y = reach(src_box.min().y(), src_box.max().y());
+            let radius = (x*x + y*y).sqrt();
+            AABB::new(
+                Point3::new(-radius, -radius, src_box.min().z()),
+                Point3::new(radius, radius, src_box.max().z()))
+        });
+        Self { source, revolutions, time0, time1, box_opt }
+    }
+
+    // Sine and cosine of how far it has turned at `time`
+    fn turn(&self, time: Float) -> (Float, Float) {
+        (2.0*PI * self.revolutions * time).sin_cos()
+    }
+}
+
+fn rotate(v: &Vec3, sin_theta: Float, cos_theta: Float) -> Vec3 {
+    Vec3::new(
+        cos_theta*v.x() - sin_theta*v.y(),
+        sin_theta*v.x() + cos_theta*v.y(),
+        v.z())
+}
+
+impl Hittable for SpinZ {
+    fn bounding_box(&self, _time0: Float, _time1: Float) -> Option<AABB> {
+        self.box_opt.as_ref().map(|b| b.copy())
+    }
+
+    fn hit(&self, r:&Ray, t_min:Float, t_max:Float, rng: &mut RngGen) -> Option<HitRecord> {
+        let (sin_theta, cos_theta) = self.turn(r.time());
+        let origin = rotate(r.origin(), -sin_theta, cos_theta);
+        let direction = rotate(r.direction(), -sin_theta, cos_theta);
+        let rotated_r = Ray::new(&origin, &direction, r.time());
+
+        let mut hit = self.source.hit(&rotated_r, t_min, t_max, rng)?;
+        let p = rotate(hit.p(), sin_theta, cos_theta);
+        let normal = rotate(hit.normal(), sin_theta, cos_theta);
+        let dpdu = rotate(hit.dpdu(), sin_theta, cos_theta);
+        let dpdv = rotate(hit.dpdv(), sin_theta, cos_theta);
+        // Turning doesn't change which face the ray sees
+        let outward_normal = if hit.in_front_face() { normal } else { -normal };
+        hit.set_p(p);
+        hit.set_shading_normal(outward_normal);
+        hit.set_tangents(dpdu, dpdv);
+        Some(hit)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.source.is_emissive()
+    }
+
+    // Lights inside spin along with it
+    fn collect_lights(&self, lights: &mut HittableList) {
+        let mut inside = HittableList::new();
+        self.source.collect_lights(&mut inside);
+        for light in inside.objects() {
+            lights.add(Rc::new(SpinZ::new(light.clone(), self.revolutions, self.time0, self.time1)));
+        }
+    }
+
+    // NOTE(srp): Light sampling doesn't know the ray's time, so it aims at
+    // where the object is halfway through its time range. Both techniques
+    // use this same pdf so MIS stays unbiased, and the parts that turned
+    // away from it are still found by sampling the BSDF.
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        let (sin_theta, cos_theta) = self.turn(0.5 * (self.time0 + self.time1));
+        self.source.pdf_value(
+            &rotate(origin, -sin_theta, cos_theta),
+            &rotate(direction, -sin_theta, cos_theta),
+            rng)
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut RngGen) -> Vec3 {
+        let (sin_theta, cos_theta) = self.turn(0.5 * (self.time0 + self.time1));
+        let direction = self.source.random(&rotate(origin, -sin_theta, cos_theta), rng);
+        rotate(&direction, sin_theta, cos_theta)
+    }
+}
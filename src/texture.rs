@@ -5,6 +5,12 @@ use super::color::Color;
 
 pub trait Texture {
     fn value(&self, u: Float, v: Float, p: &Point3) -> Color;
+
+    // Opacity in [0, 1], used by AlphaMask. Textures without an alpha of
+    // their own use their first channel, like MixMaterial's mask.
+    fn alpha(&self, u: Float, v: Float, p: &Point3) -> Float {
+        self.value(u, v, p).x().clamp(0.0, 1.0)
+    }
 }
 
 pub struct SolidColor {
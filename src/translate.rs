@@ -1,7 +1,9 @@
 use super::hittable::{Hittable, HitRecord};
+use super::hittable_list::HittableList;
 use super::float::Float;
 use super::rng_float::RngGen;
 use super::vec3::Vec3;
+type Point3 = Vec3;
 use super::ray::Ray;
 use super::aabb::AABB;
 
@@ -39,4 +41,25 @@ impl Hittable for Translate {
             None => None
         }
     }
+
+    fn is_emissive(&self) -> bool {
+        self.source.is_emissive()
+    }
+
+    // Lights inside get moved along with it
+    fn collect_lights(&self, lights: &mut HittableList) {
+        let mut inside = HittableList::new();
+        self.source.collect_lights(&mut inside);
+        for light in inside.objects() {
+            lights.add(Rc::new(Translate::new(light.clone(), self.offset.copy())));
+        }
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3, rng: &mut RngGen) -> Float {
+        self.source.pdf_value(&(origin - &self.offset), direction, rng)
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut RngGen) -> Vec3 {
+        self.source.random(&(origin - &self.offset), rng)
+    }
 }
@@ -67,6 +67,28 @@ impl Vec3 {
         }
     }
 
+    // Cosine weighted direction around +z
+    pub fn random_cosine_direction(rng: &mut RngGen) -> Self {
+        let r1 = rng.get();
+        let r2 = rng.get();
+        let phi = 2.0*PI*r1;
+        let r2_sqrt = r2.sqrt();
+        Self::new(phi.cos()*r2_sqrt, phi.sin()*r2_sqrt, (1.0 - r2).sqrt())
+    }
+
+    // Uniform direction around +z no further than acos(cos_theta_max) from it
+    // Takes 1 - cos_theta_max, narrow cones would round to their axis
+    // going through cos_theta_max
+    pub fn random_cone_direction(rng: &mut RngGen, one_minus_cos_max: Float) -> Self {
+        let r1 = rng.get();
+        let r2 = rng.get();
+        let one_minus_z = r2*one_minus_cos_max;
+        let z = 1.0 - one_minus_z;
+        let phi = 2.0*PI*r1;
+        let sin_theta = (one_minus_z*(2.0 - one_minus_z)).max(0.0).sqrt();
+        Self::new(phi.cos()*sin_theta, phi.sin()*sin_theta, z)
+    }
+
     pub fn new(x: Float, y: Float, z:Float) -> Self {
         Self { x, y, z }
     }